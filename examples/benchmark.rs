@@ -0,0 +1,93 @@
+#![allow(clippy::print_stdout)]
+
+use algx::SolverBuilder;
+
+use std::time::Instant;
+
+/// Hard sudokus, with zeros for the empty cells
+const PUZZLES: [&str; 4] = [
+    "000000010400000000020000000000050407008000300001090000300400200050100000000806000",
+    "100007090030020008009600500005300900010080002600004000300000010040000007007000300",
+    "800000000003600000070090200050007000000045700000100030001000068008500010090000400",
+    "000000000000003085001020000000507000004000100090000000500000073002010000000040009",
+];
+
+/// Times building and searching sudokus, whose rows all have four columns. Run it with
+/// `cargo run --release --example benchmark`, along with the pentomino example.
+fn main() {
+    let seconds = best_of(5, || {
+        for _ in 0..20 {
+            for puzzle in PUZZLES {
+                assert_eq!(Ok(1), sudoku(3, puzzle).build().count_solutions());
+            }
+        }
+    });
+    println!(
+        "{} hard sudokus: {:.1} ms",
+        20 * PUZZLES.len(),
+        seconds * 1e3
+    );
+
+    let seconds = best_of(5, || {
+        sudoku(3, "").build().count_solutions_up_to(200_000);
+    });
+    println!(
+        "First 200000 solutions of an empty sudoku: {:.1} ms",
+        seconds * 1e3
+    );
+
+    let builder = sudoku(10, "");
+    let seconds = best_of(3, || {
+        builder.clone().build();
+    });
+    println!(
+        "Building an empty 100x100 sudoku of 4 million nodes: {:.1} ms",
+        seconds * 1e3
+    );
+}
+
+/// Returns the shortest time in seconds of running the function the given number of times
+fn best_of(runs: usize, mut f: impl FnMut()) -> f64 {
+    (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed().as_secs_f64()
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Returns a sudoku with boxes of `n` by `n` cells. The givens are digits from 1 to 9
+/// for a sudoku of the usual size, and zeros or nothing for the empty cells.
+fn sudoku(n: usize, givens: &str) -> SolverBuilder {
+    let size = n * n;
+    let cells = size * size;
+
+    // Columns for each cell, each number in each row, column and box
+    let mut builder = SolverBuilder::new();
+    for y in 0..size {
+        for x in 0..size {
+            let b = (y / n) * n + x / n;
+            for num in 0..size {
+                builder.add_row(vec![
+                    y * size + x,
+                    cells + y * size + num,
+                    2 * cells + x * size + num,
+                    3 * cells + b * size + num,
+                ]);
+            }
+        }
+    }
+
+    let initial_rows = givens
+        .chars()
+        .enumerate()
+        .filter_map(|(cell, c)| {
+            let num = c.to_digit(10)? as usize;
+            (num > 0).then(|| cell * size + num - 1)
+        })
+        .collect();
+    builder.set_initial_rows(initial_rows);
+
+    builder
+}
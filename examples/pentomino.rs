@@ -0,0 +1,122 @@
+#![allow(clippy::print_stdout)]
+
+use algx::SolverBuilder;
+
+use std::collections::BTreeSet;
+use std::time::Instant;
+
+const WIDTH: i32 = 10;
+const HEIGHT: i32 = 6;
+
+/// Cells of the twelve pentominoes, named by the letters they resemble
+const PIECES: [(char, [(i32, i32); 5]); 12] = [
+    ('I', [(0, 0), (1, 0), (2, 0), (3, 0), (4, 0)]),
+    ('V', [(0, 0), (0, 1), (0, 2), (1, 2), (2, 2)]),
+    ('T', [(0, 0), (1, 0), (2, 0), (1, 1), (1, 2)]),
+    ('W', [(0, 0), (1, 0), (1, 1), (2, 1), (2, 2)]),
+    ('X', [(1, 0), (0, 1), (1, 1), (2, 1), (1, 2)]),
+    ('L', [(0, 0), (1, 0), (2, 0), (3, 0), (3, 1)]),
+    ('N', [(0, 0), (1, 0), (2, 0), (2, 1), (3, 1)]),
+    ('P', [(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)]),
+    ('U', [(0, 0), (1, 0), (2, 0), (0, 1), (2, 1)]),
+    ('F', [(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)]),
+    ('Y', [(0, 0), (1, 0), (2, 0), (3, 0), (1, 1)]),
+    ('Z', [(0, 0), (1, 0), (1, 1), (1, 2), (2, 2)]),
+];
+
+/// Placement of a piece on the board
+struct Placement {
+    piece: usize,
+    cells: Vec<(i32, i32)>,
+}
+
+/// Counts the ways to tile a 6x10 rectangle with the twelve pentominoes, which also
+/// serves as a benchmark of the search. Run it with `cargo run --release --example pentomino`.
+fn main() {
+    let placements = placements();
+
+    // Column `piece` is the piece, and the columns after the pieces are the cells
+    let mut builder = SolverBuilder::new();
+    for Placement { piece, cells } in &placements {
+        let mut row = vec![*piece];
+        row.extend(
+            cells
+                .iter()
+                .map(|(x, y)| PIECES.len() + (y * WIDTH + x) as usize),
+        );
+        builder.add_row(row);
+    }
+
+    let start = Instant::now();
+    let count = builder.clone().build().count_solutions().unwrap();
+
+    println!(
+        "{} tilings, counted in {:.2} s",
+        count,
+        start.elapsed().as_secs_f64()
+    );
+    println!();
+
+    let mut board = [['.'; WIDTH as usize]; HEIGHT as usize];
+    for row in builder.build().next().unwrap() {
+        let Placement { piece, cells } = &placements[row];
+        for (x, y) in cells {
+            board[*y as usize][*x as usize] = PIECES[*piece].0;
+        }
+    }
+    for line in board {
+        println!("{}", line.iter().collect::<String>());
+    }
+}
+
+/// Returns every placement of every piece in each of its distinct orientations
+fn placements() -> Vec<Placement> {
+    let mut placements = vec![];
+
+    for (piece, (_, cells)) in PIECES.iter().enumerate() {
+        let mut orientations = BTreeSet::new();
+
+        for orientation in 0..8 {
+            let mut cells = cells
+                .iter()
+                .map(|(x, y)| {
+                    let (x, y) = if orientation & 1 == 1 {
+                        (-x, *y)
+                    } else {
+                        (*x, *y)
+                    };
+                    match orientation / 2 {
+                        0 => (x, y),
+                        1 => (-y, x),
+                        2 => (-x, -y),
+                        _ => (y, -x),
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            let min_x = cells.iter().map(|(x, _)| *x).min().unwrap();
+            let min_y = cells.iter().map(|(_, y)| *y).min().unwrap();
+            for (x, y) in &mut cells {
+                *x -= min_x;
+                *y -= min_y;
+            }
+            cells.sort_unstable();
+            orientations.insert(cells);
+        }
+
+        for cells in orientations {
+            for dy in 0..HEIGHT {
+                for dx in 0..WIDTH {
+                    if cells.iter().all(|(x, y)| x + dx < WIDTH && y + dy < HEIGHT) {
+                        placements.push(Placement {
+                            piece,
+                            cells: cells.iter().map(|(x, y)| (x + dx, y + dy)).collect(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    placements
+}
@@ -1,16 +1,31 @@
 #![allow(clippy::print_stdout)]
 
-use algx::Solver;
+use algx::Problem;
 
-fn main() {
-    let rows = create_sudoku_exact_cover();
+/// A constraint of a sudoku that must be satisfied exactly once
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Constraint {
+    Cell { x: usize, y: usize },
+    Row { y: usize, num: usize },
+    Column { x: usize, num: usize },
+    Box { b: usize, num: usize },
+}
 
-    let solver = Solver::new(rows, vec![]);
+/// Placement of a number in a cell
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Placement {
+    x: usize,
+    y: usize,
+    num: usize,
+}
+
+fn main() {
+    let problem = create_sudoku_exact_cover();
 
-    for (i, solution) in solver.enumerate() {
-        let mut sudoku: [[u8; 9]; 9] = Default::default();
-        for (x, y, num) in solution.into_iter().map(x_y_num_from_row_index) {
-            sudoku[y][x] = num as u8;
+    for (i, solution) in problem.solutions().enumerate() {
+        let mut sudoku: [[usize; 9]; 9] = Default::default();
+        for Placement { x, y, num } in solution {
+            sudoku[*y][*x] = *num;
         }
 
         println!("-------------------------");
@@ -37,32 +52,27 @@ fn main() {
     }
 }
 
-fn create_sudoku_exact_cover_row(y: &usize, x: &usize, num: &usize) -> [usize; 4] {
-    [
-        y * 9 + x,
-        (9 * 9) + (x * 9) + num,
-        (9 * 9) * 2 + (y * 9) + num,
-        (9 * 9) * 3 + (((y / 3) * 3 + (x / 3)) * 9 + num),
-    ]
-}
-
-fn create_sudoku_exact_cover() -> Vec<Vec<usize>> {
-    let mut rows = vec![];
+fn create_sudoku_exact_cover() -> Problem<Constraint, Placement> {
+    let mut problem = Problem::new();
 
     for y in 0..9 {
         for x in 0..9 {
-            for num in 0..9 {
-                rows.push(create_sudoku_exact_cover_row(&y, &x, &num).to_vec());
+            for num in 1..=9 {
+                problem.add_option(
+                    Placement { x, y, num },
+                    [
+                        Constraint::Cell { x, y },
+                        Constraint::Row { y, num },
+                        Constraint::Column { x, num },
+                        Constraint::Box {
+                            b: (y / 3) * 3 + x / 3,
+                            num,
+                        },
+                    ],
+                );
             }
         }
     }
-    rows
-}
-
-fn x_y_num_from_row_index(i: usize) -> (usize, usize, usize) {
-    let num = i % 9 + 1;
-    let y = (i / 9) % 9;
-    let x = i / (9 * 9);
 
-    (x, y, num)
+    problem
 }
@@ -0,0 +1,111 @@
+use crate::{ColumnChooser, Observer, Solver};
+
+/// Assumptions are rows that are tentatively added to every solution, like the initial
+/// rows, and retracted in the reverse order. Any search in progress is discarded, and
+/// the search starts again from the beginning after each push and pop.
+///
+/// ```
+/// use algx::Solver;
+///
+/// let mut solver = Solver::new(vec![vec![0, 1], vec![0], vec![1], vec![1, 2], vec![2]], vec![]);
+/// assert_eq!(Ok(3), solver.count_solutions());
+///
+/// assert!(solver.push_assumption(2));
+/// assert_eq!(vec![vec![2, 1, 4]], solver.clone().collect::<Vec<_>>());
+///
+/// // Rows 2 and 3 can not be in the same solution
+/// assert!(!solver.push_assumption(3));
+/// assert_eq!(Ok(0), solver.count_solutions());
+///
+/// solver.pop_assumption();
+/// solver.pop_assumption();
+/// assert_eq!(Ok(3), solver.count_solutions());
+/// ```
+impl<C: ColumnChooser, O: Observer> Solver<C, O> {
+    /// Adds the row to every solution until it is popped. Returns whether the row
+    /// can be in the same solution with the initial rows and earlier assumptions.
+    /// If not, there are no solutions until the row is popped.
+    pub fn push_assumption(&mut self, row: usize) -> bool {
+        self.unwind();
+        self.initial.assumptions.push(row);
+
+        let conflict = self.selection_conflict().is_some();
+        self.prepare();
+
+        !conflict
+    }
+
+    /// Removes the most recently pushed assumption and returns its row
+    pub fn pop_assumption(&mut self) -> Option<usize> {
+        self.unwind();
+        let row = self.initial.assumptions.pop();
+        self.prepare();
+
+        row
+    }
+
+    /// Rows that are currently assumed, in the order they were pushed
+    pub fn assumptions(&self) -> &[usize] {
+        &self.initial.assumptions
+    }
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod tests {
+    use crate::tests::n_queens;
+    use crate::SolverBuilder;
+
+    #[test]
+    fn test_push_and_pop() {
+        let mut solver = n_queens(6).build();
+        assert_eq!(Ok(4), solver.clone().count_solutions());
+
+        // Queen at the second square of the first rank
+        assert!(solver.push_assumption(1));
+        assert_eq!(Ok(1), solver.clone().count_solutions());
+        assert!(solver.clone().all(|solution| solution[0] == 1));
+
+        // Queen next to it on the second rank is attacked diagonally
+        assert!(!solver.push_assumption(6 + 2));
+        assert_eq!(Ok(0), solver.clone().count_solutions());
+        assert_eq!(Some(8), solver.pop_assumption());
+
+        // Queen elsewhere on the second rank is not attacked, but leads nowhere
+        assert!(solver.push_assumption(6 + 4));
+        assert_eq!(Ok(0), solver.clone().count_solutions());
+        assert_eq!(&[1, 10], solver.assumptions());
+
+        assert_eq!(Some(10), solver.pop_assumption());
+        assert_eq!(Some(1), solver.pop_assumption());
+        assert_eq!(None, solver.pop_assumption());
+        assert_eq!(Ok(4), solver.count_solutions());
+    }
+
+    #[test]
+    fn test_with_initial_rows_and_edits() {
+        let mut builder = SolverBuilder::new();
+        builder.set_rows(vec![vec![0, 2], vec![1, 2], vec![0], vec![1], vec![0, 1]]);
+        builder.set_column_bounds(2, 1..=2);
+        builder.set_initial_rows(vec![0]);
+
+        let mut solver = builder.build();
+        assert_eq!(vec![vec![0, 1], vec![0, 3]], solver.clone().collect::<Vec<_>>());
+
+        // The row uses the column that can be used twice once more
+        assert!(solver.push_assumption(1));
+        assert!(!solver.push_assumption(1));
+        solver.pop_assumption();
+
+        assert!(!solver.push_assumption(0));
+        assert!(!solver.push_assumption(4));
+        solver.pop_assumption();
+        solver.pop_assumption();
+
+        // Removing an assumed row drops the assumption
+        solver.push_assumption(3);
+        solver.remove_row(3);
+        assert_eq!(&[1], solver.assumptions());
+        assert_eq!(vec![vec![0, 1]], solver.collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,187 @@
+use crate::{ColumnChooser, Interruption, Observer, Solver};
+
+/// Whether a row is part of every solution, no solution or some of them
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RowStatus {
+    /// The row is in every solution
+    Forced,
+    /// The row is in no solution
+    Impossible,
+    /// The row is in some solutions but not in all of them
+    Free,
+}
+
+impl<C: ColumnChooser, O: Observer> Solver<C, O> {
+    /// Finds out for each row whether it is forced, impossible or free. If there are
+    /// no solutions, every row is impossible.
+    ///
+    /// Instead of enumerating every solution, this looks for a single solution with or
+    /// without each row whose status is still unknown, and each solution that is found
+    /// tells something about every row. Rows that are found to be free are skipped.
+    ///
+    /// The search is restarted before and after the analysis. If it is interrupted,
+    /// it can be retried with a new budget.
+    pub fn backbone(&mut self) -> Result<Vec<RowStatus>, Interruption> {
+        let row_count = self.state.row_nodes.len();
+        let selection = self.selection();
+
+        self.restart();
+        let first = self.try_next();
+        self.restart();
+
+        let Some(first) = first? else {
+            return Ok(vec![RowStatus::Impossible; row_count]);
+        };
+
+        // Whether each row has been in a solution, and whether it has been left out of one
+        let mut included = vec![false; row_count];
+        let mut excluded = vec![false; row_count];
+
+        record_solution(&mut included, &mut excluded, &first);
+
+        for row_idx in 0..row_count {
+            let solution = if !included[row_idx] {
+                self.solution_with(row_idx)?
+            } else if !excluded[row_idx] && !selection.contains(&row_idx) {
+                self.solution_without(row_idx)?
+            } else {
+                continue;
+            };
+
+            if let Some(solution) = solution {
+                record_solution(&mut included, &mut excluded, &solution);
+            }
+        }
+
+        Ok((0..row_count)
+            .map(|row_idx| match (included[row_idx], excluded[row_idx]) {
+                (true, true) => RowStatus::Free,
+                (true, false) => RowStatus::Forced,
+                _ => RowStatus::Impossible,
+            })
+            .collect())
+    }
+
+    /// Finds a solution that contains the row, restarting the search afterwards
+    fn solution_with(&mut self, row_idx: usize) -> Result<Option<Vec<usize>>, Interruption> {
+        self.push_assumption(row_idx);
+        let solution = self.try_next();
+        self.pop_assumption();
+
+        solution
+    }
+
+    /// Finds a solution that does not contain the row, restarting the search afterwards
+    fn solution_without(&mut self, row_idx: usize) -> Result<Option<Vec<usize>>, Interruption> {
+        self.unwind();
+        self.initial
+            .excluded_rows
+            .push(self.state.row_nodes[row_idx]);
+        self.prepare();
+
+        let solution = self.try_next();
+
+        self.unwind();
+        self.initial.excluded_rows.pop();
+        self.prepare();
+
+        solution
+    }
+}
+
+fn record_solution(included: &mut [bool], excluded: &mut [bool], solution: &[usize]) {
+    let mut in_solution = vec![false; included.len()];
+    for row_idx in solution.iter().copied() {
+        in_solution[row_idx] = true;
+        included[row_idx] = true;
+    }
+
+    for (excluded, in_solution) in excluded.iter_mut().zip(in_solution) {
+        *excluded |= !in_solution;
+    }
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod tests {
+    use crate::rng::Rng;
+    use crate::tests::{n_queens, random_colored_row};
+    use crate::{Interruption, RowStatus, SearchBudget, Solver, SolverBuilder};
+
+    fn enumerated(solver: Solver, row_count: usize) -> Vec<RowStatus> {
+        let solutions = solver.collect::<Vec<_>>();
+
+        (0..row_count).map(|row_idx| {
+            let count = solutions.iter().filter(|solution| solution.contains(&row_idx)).count();
+            match count {
+                0 => RowStatus::Impossible,
+                _ if count == solutions.len() => RowStatus::Forced,
+                _ => RowStatus::Free,
+            }
+        }).collect()
+    }
+
+    #[test]
+    fn test_n_queens_backbone() {
+        let mut builder = n_queens(6);
+        let mut solver = builder.clone().build();
+        let backbone = solver.backbone().unwrap();
+
+        assert_eq!(enumerated(builder.clone().build(), 36), backbone);
+        // The corners are in no solution
+        assert_eq!(RowStatus::Impossible, backbone[0]);
+        assert_eq!(RowStatus::Free, backbone[1]);
+        // The search starts from the beginning afterwards
+        assert_eq!(Ok(4), solver.count_solutions());
+
+        builder.set_initial_rows(vec![1]);
+        let backbone = builder.clone().build().backbone().unwrap();
+        assert_eq!(enumerated(builder.build(), 36), backbone);
+        assert_eq!(6, backbone.iter().filter(|status| **status == RowStatus::Forced).count());
+        assert_eq!(30, backbone.iter().filter(|status| **status == RowStatus::Impossible).count());
+    }
+
+    #[test]
+    fn test_no_solutions() {
+        let mut solver = Solver::new(vec![vec![0, 1], vec![1, 2]], vec![]);
+
+        assert_eq!(vec![RowStatus::Impossible; 2], solver.backbone().unwrap());
+    }
+
+    #[test]
+    fn test_interrupted() {
+        let mut solver = n_queens(8).build();
+
+        let mut budget = SearchBudget::new();
+        budget.set_max_steps(50);
+        solver.set_budget(budget);
+
+        assert_eq!(Err(Interruption::StepLimit), solver.backbone());
+
+        solver.set_budget(SearchBudget::new());
+        assert_eq!(enumerated(n_queens(8).build(), 64), solver.backbone().unwrap());
+    }
+
+    #[test]
+    fn test_against_enumeration() {
+        let mut rng = Rng::new(18);
+
+        for _ in 0..300 {
+            let primary_count = 1 + rng.below(3);
+            let column_count = primary_count + rng.below(3);
+
+            let mut builder = SolverBuilder::new();
+            builder.set_secondary_columns((primary_count..column_count).collect());
+            if rng.below(2) == 0 {
+                builder.set_column_bounds(0, rng.below(2)..=2);
+            }
+
+            let row_count = 1 + rng.below(8);
+            for _ in 0..row_count {
+                builder.add_colored_row(random_colored_row(&mut rng, primary_count, column_count));
+            }
+
+            assert_eq!(enumerated(builder.clone().build(), row_count), builder.clone().build().backbone().unwrap(), "{:?}", builder);
+        }
+    }
+}
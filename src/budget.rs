@@ -0,0 +1,250 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+/// Limits on how much work the search may do before it is interrupted.
+/// The limits are counted from the moment the budget is given to the solver.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SearchBudget {
+    max_steps: Option<u64>,
+    max_updates: Option<u64>,
+    #[cfg(not(target_arch = "wasm32"))]
+    deadline: Option<Instant>,
+}
+
+impl SearchBudget {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of search steps
+    pub fn set_max_steps(&mut self, max_steps: u64) {
+        self.max_steps = Some(max_steps);
+    }
+
+    /// Sets the maximum number of nodes removed from their columns
+    pub fn set_max_updates(&mut self, max_updates: u64) {
+        self.max_updates = Some(max_updates);
+    }
+
+    /// Sets the point in time after which the search stops
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_deadline(&mut self, deadline: Instant) {
+        self.deadline = Some(deadline);
+    }
+}
+
+/// Reason why the search stopped before it was completed
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Interruption {
+    /// The maximum number of steps was reached
+    StepLimit,
+    /// The maximum number of updates was reached
+    UpdateLimit,
+    /// The deadline has passed
+    Deadline,
+    /// The search was cancelled through a [`CancellationToken`]
+    Cancelled,
+}
+
+/// Token for cancelling a search from another thread
+#[derive(Debug, Default, Clone)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    parent: Option<Arc<AtomicBool>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+            || self
+                .parent
+                .as_ref()
+                .is_some_and(|parent| parent.load(Ordering::Relaxed))
+    }
+
+    /// Returns a new token that is also cancelled when this one is, while cancelling it
+    /// leaves this one alone
+    pub(crate) fn child(&self) -> Self {
+        Self {
+            cancelled: Arc::default(),
+            parent: Some(Arc::clone(&self.cancelled)),
+        }
+    }
+}
+
+/// Budget converted to absolute limits on the counters of a solver
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Limits {
+    pub(crate) step_limit: u64,
+    pub(crate) update_limit: u64,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) deadline: Option<Instant>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            step_limit: u64::MAX,
+            update_limit: u64::MAX,
+            #[cfg(not(target_arch = "wasm32"))]
+            deadline: None,
+        }
+    }
+}
+
+impl Limits {
+    pub(crate) fn new(budget: SearchBudget, steps: u64, updates: u64) -> Self {
+        let offset = |max: Option<u64>, current: u64| {
+            max.map_or(u64::MAX, |max| current.saturating_add(max))
+        };
+
+        Self {
+            step_limit: offset(budget.max_steps, steps),
+            update_limit: offset(budget.max_updates, updates),
+            #[cfg(not(target_arch = "wasm32"))]
+            deadline: budget.deadline,
+        }
+    }
+
+    /// Adjusts the update limit for the update counter being reset from `updates` to zero
+    pub(crate) fn rebase_updates(&mut self, updates: u64) {
+        if self.update_limit != u64::MAX {
+            self.update_limit = self.update_limit.saturating_sub(updates);
+        }
+    }
+
+    pub(crate) fn check(&self, steps: u64, updates: u64) -> Option<Interruption> {
+        if steps >= self.step_limit {
+            return Some(Interruption::StepLimit);
+        }
+
+        if updates >= self.update_limit {
+            return Some(Interruption::UpdateLimit);
+        }
+
+        // Reading the clock is relatively slow, so it is only done every now and then
+        #[cfg(not(target_arch = "wasm32"))]
+        if steps.is_multiple_of(1024)
+            && self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return Some(Interruption::Deadline);
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod tests {
+    use super::*;
+    use crate::tests::n_queens;
+
+    #[test]
+    fn test_step_budget_is_resumable() {
+        let mut solver = n_queens(8).build();
+        let mut count = 0;
+        let mut interruptions = 0;
+
+        loop {
+            let mut budget = SearchBudget::new();
+            budget.set_max_steps(100);
+            solver.set_budget(budget);
+
+            count += solver.count_solutions_up_to(u128::MAX);
+
+            match solver.interruption() {
+                Some(interruption) => {
+                    assert_eq!(Interruption::StepLimit, interruption);
+                    assert!(!solver.is_completed());
+                    interruptions += 1;
+                }
+                None => break,
+            }
+        }
+
+        assert_eq!(92, count);
+        assert!(interruptions > 10);
+        assert!(solver.is_completed());
+    }
+
+    #[test]
+    fn test_interrupted_answers() {
+        let mut budget = SearchBudget::new();
+        budget.set_max_steps(200);
+
+        let mut solver = n_queens(8).build();
+        solver.set_budget(budget);
+        assert_eq!(Err(Interruption::StepLimit), solver.uniqueness());
+
+        let mut solver = n_queens(8).build();
+        solver.set_budget(budget);
+        assert_eq!(Err(Interruption::StepLimit), solver.count_solutions());
+
+        solver.set_budget(SearchBudget::new());
+        assert!(solver.count_solutions().unwrap() < 92);
+    }
+
+    #[test]
+    fn test_update_budget() {
+        let mut solver = n_queens(8).build();
+
+        let mut budget = SearchBudget::new();
+        budget.set_max_updates(5000);
+        solver.set_budget(budget);
+
+        let mut count = 0;
+        while let Ok(Some(_)) = solver.try_next() {
+            count += 1;
+        }
+
+        assert_eq!(Some(Interruption::UpdateLimit), solver.interruption());
+
+        solver.set_budget(SearchBudget::new());
+
+        assert_eq!(Ok(92), solver.count_solutions().map(|rest| count + rest));
+        assert_eq!(Ok(None), solver.try_next());
+    }
+
+    #[test]
+    fn test_deadline() {
+        let mut solver = n_queens(10).build();
+
+        let mut budget = SearchBudget::new();
+        budget.set_deadline(Instant::now());
+        solver.set_budget(budget);
+
+        assert_eq!(Err(Interruption::Deadline), solver.try_next().and_then(|_| solver.try_next()));
+    }
+
+    #[test]
+    fn test_cancellation() {
+        let mut solver = n_queens(8).build();
+        let token = CancellationToken::new();
+        solver.set_cancellation_token(token.clone());
+
+        assert!(solver.try_next().unwrap().is_some());
+
+        token.cancel();
+
+        assert_eq!(Err(Interruption::Cancelled), solver.try_next());
+        assert_eq!(None, solver.next());
+        assert!(!solver.is_completed());
+
+        solver.set_cancellation_token(CancellationToken::new());
+
+        assert_eq!(Ok(91), solver.count_solutions());
+    }
+}
@@ -0,0 +1,241 @@
+use crate::node::MAX_COLOR;
+use crate::rng::Rng;
+use crate::{ColumnChooser, Mrv, MrvRandom, NoObserver, Observer, Solver, SolverError};
+
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+/// Builder for a [`Solver`] of a generalized exact cover problem.
+///
+/// Every column is primary (covered exactly once) unless it is marked as secondary,
+/// in which case it is covered at most once. Primary columns can also be given bounds
+/// for how many times they must be covered.
+#[derive(Debug, Default, Clone)]
+pub struct SolverBuilder {
+    pub(crate) rows: Vec<Vec<(usize, Option<usize>)>>,
+    pub(crate) columns: Vec<usize>,
+    pub(crate) secondary_columns: Vec<usize>,
+    pub(crate) column_bounds: BTreeMap<usize, RangeInclusive<usize>>,
+    pub(crate) initial_columns: Vec<usize>,
+    pub(crate) initial_rows: Vec<usize>,
+    pub(crate) seed: Option<u64>,
+}
+
+impl SolverBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a row. Columns in the row are assumed to be in ascending order
+    pub fn add_row(&mut self, row: Vec<usize>) {
+        self.rows
+            .push(row.into_iter().map(|col| (col, None)).collect());
+    }
+
+    /// Adds a row whose columns may be assigned a color. Only secondary columns
+    /// can be colored, and rows that assign different colors to the same column
+    /// can not be in the same solution. Colors must be below 2^31 - 1: building panics
+    /// on larger colors, and [`try_build`](Self::try_build) returns an error.
+    pub fn add_colored_row(&mut self, row: Vec<(usize, Option<usize>)>) {
+        self.rows.push(row);
+    }
+
+    pub fn set_rows(&mut self, rows: Vec<Vec<usize>>) {
+        self.rows.clear();
+        for row in rows {
+            self.add_row(row);
+        }
+    }
+
+    /// Adds a primary column, which no row needs to contain. Columns are otherwise
+    /// only those of the rows, those with bounds and the secondary columns, and a primary
+    /// column that no row contains leaves the problem without solutions.
+    pub fn add_column(&mut self, column: usize) {
+        self.columns.push(column);
+    }
+
+    /// Sets the columns that may be covered at most once instead of exactly once
+    pub fn set_secondary_columns(&mut self, secondary_columns: Vec<usize>) {
+        self.secondary_columns = secondary_columns;
+    }
+
+    /// Sets how many times a column must be covered. The default is exactly once.
+    /// For secondary columns only the upper bound is used.
+    pub fn set_column_bounds(&mut self, column: usize, bounds: RangeInclusive<usize>) {
+        self.column_bounds.insert(column, bounds);
+    }
+
+    /// Sets the columns that are already covered before the search starts
+    pub fn set_initial_columns(&mut self, initial_columns: Vec<usize>) {
+        self.initial_columns = initial_columns;
+    }
+
+    /// Sets the rows that are part of every solution, such as the givens of a puzzle.
+    /// Their columns are covered before the search starts, and they are included
+    /// at the start of each solution.
+    pub fn set_initial_rows(&mut self, initial_rows: Vec<usize>) {
+        self.initial_rows = initial_rows;
+    }
+
+    /// Makes the search try the rows of each column in a random order,
+    /// which is the same for the same seed.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = Some(seed);
+    }
+
+    pub fn build(self) -> Solver {
+        self.build_with_chooser(Mrv)
+    }
+
+    /// Builds a solver that uses the given strategy for choosing columns
+    pub fn build_with_chooser<C: ColumnChooser>(self, chooser: C) -> Solver<C> {
+        Solver::from_builder(self, chooser, NoObserver)
+    }
+
+    /// Builds a solver that uses the given strategy for choosing columns and sends
+    /// the events of the whole search to the observer
+    pub fn build_with_observer<C: ColumnChooser, O: Observer>(
+        self,
+        chooser: C,
+        observer: O,
+    ) -> Solver<C, O> {
+        Solver::from_builder(self, chooser, observer)
+    }
+
+    /// Builds a solver that tries rows in a random order and breaks ties between
+    /// columns randomly. The same seed always gives the same order of solutions.
+    ///
+    /// The first solution found is a random sample of all solutions,
+    /// although not a uniformly distributed one.
+    pub fn build_randomized(mut self, seed: u64) -> Solver<MrvRandom> {
+        let mut rng = Rng::new(seed);
+
+        self.set_seed(rng.next_u64());
+        self.build_with_chooser(MrvRandom::new(rng.next_u64()))
+    }
+
+    /// Builds the solver after checking that the rows and columns are valid
+    pub fn try_build(self) -> Result<Solver, SolverError> {
+        self.validate()?;
+
+        // The initial rows are checked by the solver before they are selected
+        let mut solver = self.build();
+        solver.unwind();
+        if let Some(err) = solver.selection_conflict() {
+            return Err(err);
+        }
+        solver.prepare();
+
+        Ok(solver)
+    }
+
+    fn validate(&self) -> Result<(), SolverError> {
+        if let Some(row_idx) = self.rows.iter().position(Vec::is_empty) {
+            return Err(SolverError::EmptyRow { row: row_idx });
+        }
+
+        self.validate_allowing_empty_rows()
+    }
+
+    /// Checks the rows and columns like [`try_build`](Self::try_build) does, except that
+    /// rows may be empty like the rows removed from a solver
+    pub(crate) fn validate_allowing_empty_rows(&self) -> Result<(), SolverError> {
+        for (row_idx, row) in self.rows.iter().enumerate() {
+            for pair in row.windows(2) {
+                let (a, b) = (pair[0].0, pair[1].0);
+
+                if a == b {
+                    return Err(SolverError::DuplicateColumn {
+                        row: row_idx,
+                        column: a,
+                    });
+                }
+
+                if a > b {
+                    return Err(SolverError::UnsortedColumns { row: row_idx });
+                }
+            }
+
+            for (col_idx, color) in row {
+                if color.is_some() && !self.secondary_columns.contains(col_idx) {
+                    return Err(SolverError::ColoredPrimaryColumn {
+                        row: row_idx,
+                        column: *col_idx,
+                    });
+                }
+
+                if color.is_some_and(|color| color > MAX_COLOR) {
+                    return Err(SolverError::ColorTooLarge {
+                        row: row_idx,
+                        column: *col_idx,
+                    });
+                }
+            }
+        }
+
+        for (col_idx, bounds) in &self.column_bounds {
+            if *bounds.end() == 0 || bounds.start() > bounds.end() {
+                return Err(SolverError::InvalidColumnBounds { column: *col_idx });
+            }
+        }
+
+        for (i, col_idx) in self.initial_columns.iter().enumerate() {
+            if self.initial_columns[..i].contains(col_idx) {
+                return Err(SolverError::ConflictingCoveredColumn { column: *col_idx });
+            }
+
+            if !self.rows.iter().flatten().any(|(c, _)| c == col_idx) {
+                return Err(SolverError::UncoverableColumn { column: *col_idx });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the ids of the columns that the solver has, with repetitions
+    pub(crate) fn declared_columns(&self) -> impl Iterator<Item = usize> + '_ {
+        self.rows
+            .iter()
+            .flatten()
+            .map(|(col_idx, _)| col_idx)
+            .chain(&self.columns)
+            .chain(&self.secondary_columns)
+            .chain(self.column_bounds.keys())
+            .chain(&self.initial_columns)
+            .copied()
+    }
+
+    /// Renumbers the columns to a dense range if their ids are too sparse to allocate
+    /// per-column data up to the largest id. Returns the original id of each renumbered
+    /// column, or nothing if the columns are kept as they are.
+    pub(crate) fn compact_columns(&mut self) -> Vec<usize> {
+        let node_count = self.rows.iter().map(Vec::len).sum::<usize>();
+        let max_column = self.declared_columns().max();
+
+        if max_column.is_none_or(|col_idx| col_idx < 2 * node_count + 64) {
+            return vec![];
+        }
+
+        let mut column_ids = self.declared_columns().collect::<Vec<_>>();
+        column_ids.sort_unstable();
+        column_ids.dedup();
+
+        let dense = |col_idx: &mut usize| *col_idx = column_ids.binary_search(col_idx).unwrap();
+
+        for (col_idx, _) in self.rows.iter_mut().flatten() {
+            dense(col_idx);
+        }
+        self.columns.iter_mut().for_each(dense);
+        self.secondary_columns.iter_mut().for_each(dense);
+        self.initial_columns.iter_mut().for_each(dense);
+        self.column_bounds = std::mem::take(&mut self.column_bounds)
+            .into_iter()
+            .map(|(mut col_idx, bounds)| {
+                dense(&mut col_idx);
+                (col_idx, bounds)
+            })
+            .collect();
+
+        column_ids
+    }
+}
@@ -0,0 +1,256 @@
+use crate::node::NodeId;
+use crate::rng::Rng;
+use crate::SolverState;
+
+use std::collections::HashMap;
+
+/// Strategy for choosing the column to branch on at each step of the search.
+///
+/// The choice does not affect which solutions are found, only their order and
+/// how much work it takes to find them.
+pub trait ColumnChooser {
+    /// Chooses one of the given columns. Only primary columns that are not yet
+    /// covered are given, and there is always at least one of them.
+    /// Returning `None` falls back to the first column.
+    fn choose(&mut self, columns: Columns<'_>) -> Option<Column>;
+}
+
+/// Primary column that can be branched on
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Column {
+    pub(crate) node_id: NodeId,
+    index: usize,
+    size: usize,
+    branches: usize,
+}
+
+impl Column {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Number of rows left in the column
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Number of branches the search would take on the column. This is the number
+    /// of rows left in the column, adjusted for how many times it must still be covered.
+    pub fn branches(&self) -> usize {
+        self.branches
+    }
+}
+
+/// Iterator over the columns that can be branched on, in ascending order
+#[derive(Debug, Clone)]
+pub struct Columns<'a> {
+    state: &'a SolverState,
+    current_id: NodeId,
+}
+
+impl<'a> Columns<'a> {
+    pub(crate) fn new(state: &'a SolverState) -> Self {
+        Self {
+            state,
+            current_id: state.nodes.right[state.header],
+        }
+    }
+}
+
+impl Iterator for Columns<'_> {
+    type Item = Column;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current_id == self.state.header {
+            return None;
+        }
+
+        let col = self.state.nodes.col(self.current_id);
+        let column = Column {
+            node_id: self.current_id,
+            index: self.state.column_id(col),
+            size: self.state.column_sizes[col],
+            branches: self.state.node_column_branches(self.current_id),
+        };
+
+        self.current_id = self.state.nodes.right[self.current_id];
+
+        Some(column)
+    }
+}
+
+/// Chooses the column with the fewest branches, preferring the first one on ties.
+/// This is the minimum remaining values heuristic recommended by Knuth.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Mrv;
+
+impl ColumnChooser for Mrv {
+    fn choose(&mut self, columns: Columns<'_>) -> Option<Column> {
+        let mut best_column = None;
+        let mut best_branches = usize::MAX;
+
+        for column in columns {
+            if column.branches < best_branches {
+                best_branches = column.branches;
+                best_column = Some(column);
+            }
+        }
+
+        best_column
+    }
+}
+
+/// Chooses the first column
+#[derive(Debug, Default, Copy, Clone)]
+pub struct FirstColumn;
+
+impl ColumnChooser for FirstColumn {
+    fn choose(&mut self, mut columns: Columns<'_>) -> Option<Column> {
+        columns.next()
+    }
+}
+
+/// Chooses the column with the fewest branches, breaking ties randomly
+#[derive(Debug, Default, Copy, Clone)]
+pub struct MrvRandom {
+    rng: Rng,
+}
+
+impl MrvRandom {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl ColumnChooser for MrvRandom {
+    fn choose(&mut self, columns: Columns<'_>) -> Option<Column> {
+        let mut best_column = None;
+        let mut best_branches = usize::MAX;
+        let mut ties = 0;
+
+        for column in columns {
+            if column.branches < best_branches {
+                best_branches = column.branches;
+                best_column = Some(column);
+                ties = 1;
+            } else if column.branches == best_branches {
+                ties += 1;
+                if self.rng.below(ties) == 0 {
+                    best_column = Some(column);
+                }
+            }
+        }
+
+        best_column
+    }
+}
+
+/// Chooses the column with the fewest branches, breaking ties by choosing the column
+/// with the highest priority. Columns without a given priority have a priority of zero.
+#[derive(Debug, Default, Clone)]
+pub struct MrvPriority {
+    priorities: HashMap<usize, i64>,
+}
+
+impl MrvPriority {
+    /// Creates the chooser from pairs of a column and its priority. A column that is
+    /// given more than once has the last of its priorities.
+    pub fn new(priorities: &[(usize, i64)]) -> Self {
+        Self {
+            priorities: priorities.iter().copied().collect(),
+        }
+    }
+
+    fn priority(&self, column: &Column) -> i64 {
+        self.priorities.get(&column.index).copied().unwrap_or(0)
+    }
+}
+
+impl ColumnChooser for MrvPriority {
+    fn choose(&mut self, columns: Columns<'_>) -> Option<Column> {
+        columns.min_by_key(|column| (column.branches, -self.priority(column)))
+    }
+}
+
+/// Chooses the column with the fewest branches among the preferred columns, and only
+/// considers the other columns once every preferred column is covered. This is
+/// Knuth's preference for "sharp" items, whose names start with `#`.
+#[derive(Debug, Default, Clone)]
+pub struct Sharp {
+    /// Preferred columns in ascending order
+    preferred: Vec<usize>,
+}
+
+impl Sharp {
+    pub fn new(preferred_columns: &[usize]) -> Self {
+        let mut preferred = preferred_columns.to_vec();
+        preferred.sort_unstable();
+
+        Self { preferred }
+    }
+
+    fn is_preferred(&self, column: &Column) -> bool {
+        self.preferred.binary_search(&column.index).is_ok()
+    }
+}
+
+impl ColumnChooser for Sharp {
+    fn choose(&mut self, columns: Columns<'_>) -> Option<Column> {
+        columns.min_by_key(|column| (!self.is_preferred(column), column.branches))
+    }
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod tests {
+    use super::*;
+    use crate::tests::sorted;
+    use crate::SolverBuilder;
+
+    fn dominoes() -> SolverBuilder {
+        // Tilings of a 2x5 board with dominoes. Columns are the cells of the board.
+        let mut builder = SolverBuilder::new();
+
+        for y in 0..2 {
+            for x in 0..5 {
+                if x + 1 < 5 {
+                    builder.add_row(vec![y * 5 + x, y * 5 + x + 1]);
+                }
+                if y == 0 {
+                    builder.add_row(vec![x, 5 + x]);
+                }
+            }
+        }
+
+        builder
+    }
+
+    #[test]
+    fn test_choosers_find_same_solutions() {
+        let expected = sorted(dominoes().build());
+
+        assert_eq!(8, expected.len());
+        assert_eq!(expected, sorted(dominoes().build_with_chooser(FirstColumn)));
+        assert_eq!(expected, sorted(dominoes().build_with_chooser(MrvRandom::new(7))));
+        assert_eq!(expected, sorted(dominoes().build_with_chooser(MrvPriority::new(&[(4, 5)]))));
+        assert_eq!(expected, sorted(dominoes().build_with_chooser(Sharp::new(&[9]))));
+    }
+
+    #[test]
+    fn test_chooser_order() {
+        // Every column has two rows
+        let mut builder = SolverBuilder::new();
+        builder.set_rows(vec![
+            vec![0],
+            vec![0, 1],
+            vec![1, 2],
+            vec![2],
+        ]);
+
+        assert_eq!(vec![vec![0, 2], vec![1, 3]], builder.clone().build_with_chooser(FirstColumn).collect::<Vec<_>>());
+        assert_eq!(vec![vec![1, 3], vec![2, 0]], builder.clone().build_with_chooser(MrvPriority::new(&[(0, 0), (1, 1)])).collect::<Vec<_>>());
+        assert_eq!(vec![vec![2, 0], vec![3, 1]], builder.build_with_chooser(Sharp::new(&[2])).collect::<Vec<_>>());
+    }
+}
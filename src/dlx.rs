@@ -0,0 +1,357 @@
+use crate::{ParseError, Solver, SolverBuilder};
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// A problem in the text format of Knuth's DLX programs, along with the names
+/// of its items and colors.
+///
+/// The first line lists the names of the items, with the secondary items separated
+/// from the primary ones by `|`. A primary item can be given a multiplicity as
+/// `u:v|name`, or `v|name` when `u` equals `v`, meaning that it must be covered
+/// between `u` and `v` times. Each following line is an option listing the names
+/// of its items, and an item of a secondary column can be given a color as `name:color`.
+/// Lines starting with `|` are comments.
+///
+/// ```text
+/// | A comment
+/// a b c | x y
+/// a b x:A
+/// c y:B
+/// b c x:B
+/// a y:B
+/// ```
+///
+/// Items are numbered as columns and options as rows in the order they are listed.
+#[derive(Debug, Clone)]
+pub struct DlxProblem {
+    builder: SolverBuilder,
+    item_names: Vec<String>,
+    color_names: Vec<String>,
+}
+
+impl DlxProblem {
+    /// Parses a problem in the DLX text format
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        let mut lines = input
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('|'));
+
+        let (item_line_number, item_line) = lines.next().ok_or(ParseError::MissingItems)?;
+
+        let mut builder = SolverBuilder::new();
+        let mut item_names = vec![];
+        let mut item_indices = HashMap::new();
+        let mut secondary = false;
+
+        for token in item_line.split_whitespace() {
+            if token == "|" {
+                secondary = true;
+                continue;
+            }
+
+            let name = match token.split_once('|') {
+                Some((multiplicity, name)) => {
+                    let invalid = || ParseError::InvalidMultiplicity {
+                        line: item_line_number,
+                        item: name.to_owned(),
+                    };
+
+                    let (min, max) = match multiplicity.split_once(':') {
+                        Some((min, max)) => (min.parse(), max.parse()),
+                        None => (multiplicity.parse(), multiplicity.parse()),
+                    };
+                    let (min, max): (usize, usize) = (
+                        min.map_err(|_err| invalid())?,
+                        max.map_err(|_err| invalid())?,
+                    );
+
+                    if max == 0 || min > max {
+                        return Err(invalid());
+                    }
+                    if (min, max) != (1, 1) {
+                        builder.set_column_bounds(item_names.len(), min..=max);
+                    }
+
+                    name
+                }
+                None => token,
+            };
+
+            if item_indices.insert(name, item_names.len()).is_some() {
+                return Err(ParseError::DuplicateItem {
+                    line: item_line_number,
+                    item: name.to_owned(),
+                });
+            }
+
+            if secondary {
+                builder.secondary_columns.push(item_names.len());
+            } else {
+                builder.add_column(item_names.len());
+            }
+            item_names.push(name.to_owned());
+        }
+
+        let mut color_names: Vec<String> = vec![];
+        let mut color_indices = HashMap::new();
+
+        for (line_number, line) in lines {
+            let mut row = vec![];
+
+            for token in line.split_whitespace() {
+                let (name, color) = match token.split_once(':') {
+                    Some((name, color)) => (name, Some(color)),
+                    None => (token, None),
+                };
+
+                let Some(&col_idx) = item_indices.get(name) else {
+                    return Err(ParseError::UnknownItem {
+                        line: line_number,
+                        item: name.to_owned(),
+                    });
+                };
+
+                let color = match color {
+                    Some(_) if !builder.secondary_columns.contains(&col_idx) => {
+                        return Err(ParseError::ColoredPrimaryItem {
+                            line: line_number,
+                            item: name.to_owned(),
+                        });
+                    }
+                    Some(color) => Some(*color_indices.entry(color).or_insert_with(|| {
+                        color_names.push(color.to_owned());
+                        color_names.len() - 1
+                    })),
+                    None => None,
+                };
+
+                row.push((col_idx, color));
+            }
+
+            row.sort_unstable_by_key(|(col_idx, _)| *col_idx);
+
+            if let Some(pair) = row.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+                return Err(ParseError::DuplicateOptionItem {
+                    line: line_number,
+                    item: item_names[pair[0].0].clone(),
+                });
+            }
+
+            builder.add_colored_row(row);
+        }
+
+        Ok(Self {
+            builder,
+            item_names,
+            color_names,
+        })
+    }
+
+    /// Creates a problem from a builder, naming the items and colors by their indices.
+    ///
+    /// The builder is kept as is, so its initial columns and rows still apply when the
+    /// problem is built. The DLX format has no way to express them though, so they are
+    /// left out when the problem is formatted.
+    pub fn from_builder(builder: SolverBuilder) -> Self {
+        let columns = builder
+            .declared_columns()
+            .max()
+            .map_or(0, |col_idx| col_idx + 1);
+
+        let colors = builder
+            .rows
+            .iter()
+            .flatten()
+            .filter_map(|(_, color)| *color)
+            .max()
+            .map_or(0, |color| color + 1);
+
+        Self {
+            builder,
+            item_names: (0..columns).map(|i| i.to_string()).collect(),
+            color_names: (0..colors).map(|i| i.to_string()).collect(),
+        }
+    }
+
+    /// Names of the items, indexed by column
+    pub fn item_names(&self) -> &[String] {
+        &self.item_names
+    }
+
+    /// Names of the colors, indexed by the colors of the builder
+    pub fn color_names(&self) -> &[String] {
+        &self.color_names
+    }
+
+    /// Returns the column of the item with the given name
+    pub fn item(&self, name: &str) -> Option<usize> {
+        self.item_names.iter().position(|item| item == name)
+    }
+
+    pub fn builder(&self) -> &SolverBuilder {
+        &self.builder
+    }
+
+    pub fn into_builder(self) -> SolverBuilder {
+        self.builder
+    }
+
+    pub fn build(self) -> Solver {
+        self.builder.build()
+    }
+
+    /// Formats the option of the given row as a line of the DLX format,
+    /// for example to print the options of a solution
+    pub fn format_option(&self, row: usize) -> String {
+        self.builder.rows[row]
+            .iter()
+            .map(|(col_idx, color)| match color {
+                Some(color) => {
+                    format!("{}:{}", self.item_names[*col_idx], self.color_names[*color])
+                }
+                None => self.item_names[*col_idx].clone(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn format_item(&self, col_idx: usize) -> String {
+        let name = &self.item_names[col_idx];
+
+        match self.builder.column_bounds.get(&col_idx) {
+            Some(bounds) if bounds.start() == bounds.end() => format!("{}|{}", bounds.end(), name),
+            Some(bounds) => format!("{}:{}|{}", bounds.start(), bounds.end(), name),
+            None => name.clone(),
+        }
+    }
+}
+
+impl FromStr for DlxProblem {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// Writes the problem in the DLX text format
+impl fmt::Display for DlxProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (secondary, primary): (Vec<usize>, Vec<usize>) = (0..self.item_names.len())
+            .partition(|col_idx| self.builder.secondary_columns.contains(col_idx));
+
+        let mut items = primary
+            .into_iter()
+            .map(|col_idx| self.format_item(col_idx))
+            .collect::<Vec<_>>();
+
+        if !secondary.is_empty() {
+            items.push("|".to_owned());
+            items.extend(
+                secondary
+                    .into_iter()
+                    .map(|col_idx| self.format_item(col_idx)),
+            );
+        }
+
+        writeln!(f, "{}", items.join(" "))?;
+
+        for row in 0..self.builder.rows.len() {
+            writeln!(f, "{}", self.format_option(row))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod tests {
+    use crate::tests::sorted;
+    use crate::{DlxProblem, ParseError, SolverBuilder};
+
+    const PROBLEM: &str = "\
+| Knuth's example of colors
+p q r | x y
+p q x y:A
+p r x:A y
+p x:B
+q x:A
+r y:B
+";
+
+    #[test]
+    fn test_parse() {
+        let problem = DlxProblem::parse(PROBLEM).unwrap();
+
+        assert_eq!(["p", "q", "r", "x", "y"], problem.item_names());
+        assert_eq!(["A", "B"], problem.color_names());
+        assert_eq!(Some(3), problem.item("x"));
+        assert_eq!(vec![3, 4], problem.builder().secondary_columns);
+        assert_eq!(vec![(0, None), (1, None), (3, None), (4, Some(0))], problem.builder().rows[0]);
+
+        assert_eq!(vec![vec![1, 3]], sorted(problem.build()));
+    }
+
+    #[test]
+    fn test_multiplicities() {
+        let problem = DlxProblem::parse("2|a 0:2|b c\na\na b\nb c\nc\n").unwrap();
+
+        assert_eq!(Some(&(2..=2)), problem.builder().column_bounds.get(&0));
+        assert_eq!(Some(&(0..=2)), problem.builder().column_bounds.get(&1));
+        assert_eq!(None, problem.builder().column_bounds.get(&2));
+        assert_eq!(Ok(2), problem.build().count_solutions());
+    }
+
+    #[test]
+    fn test_unused_items() {
+        // No option covers the primary item b, while the secondary item x may stay uncovered
+        assert_eq!(Ok(0), DlxProblem::parse("a b\na\n").unwrap().build().count_solutions());
+        assert_eq!(Ok(1), DlxProblem::parse("a | x\na\n").unwrap().build().count_solutions());
+        assert_eq!(Ok(1), DlxProblem::parse("a 0:1|b\na\n").unwrap().build().count_solutions());
+
+        let problem = DlxProblem::parse("a b\na\n").unwrap();
+        let problem = DlxProblem::from_builder(problem.into_builder());
+        assert_eq!("0 1\n0\n", problem.to_string());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let input = "2|a 0:2|b c | x y\na b x:A\nb c\nc x:B y\na y:A\n";
+        let problem = DlxProblem::parse(input).unwrap();
+
+        assert_eq!(input, problem.to_string());
+        assert_eq!(input, problem.to_string().parse::<DlxProblem>().unwrap().to_string());
+        assert_eq!("c x:B y", problem.format_option(2));
+    }
+
+    #[test]
+    fn test_from_builder() {
+        let mut builder = SolverBuilder::new();
+        builder.add_row(vec![0, 1]);
+        builder.add_colored_row(vec![(1, None), (2, Some(1))]);
+        builder.set_secondary_columns(vec![2]);
+        builder.set_column_bounds(1, 1..=2);
+        builder.set_initial_rows(vec![1]);
+
+        let problem = DlxProblem::from_builder(builder);
+
+        assert_eq!("0 1:2|1 | 2\n0 1\n1 2:1\n", problem.to_string());
+        assert_eq!(Ok(1), problem.build().count_solutions());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(ParseError::MissingItems, DlxProblem::parse("| only a comment\n\n").unwrap_err());
+        assert_eq!(ParseError::DuplicateItem { line: 1, item: "a".into() }, DlxProblem::parse("a b a\n").unwrap_err());
+        assert_eq!(ParseError::InvalidMultiplicity { line: 1, item: "a".into() }, DlxProblem::parse("2:1|a\n").unwrap_err());
+        assert_eq!(ParseError::InvalidMultiplicity { line: 1, item: "a".into() }, DlxProblem::parse("0|a\n").unwrap_err());
+        assert_eq!(ParseError::UnknownItem { line: 3, item: "c".into() }, DlxProblem::parse("a b\n\na c\n").unwrap_err());
+        assert_eq!(ParseError::DuplicateOptionItem { line: 2, item: "b".into() }, DlxProblem::parse("a b\nb a b\n").unwrap_err());
+        assert_eq!(ParseError::ColoredPrimaryItem { line: 2, item: "a".into() }, DlxProblem::parse("a | b\na:A b\n").unwrap_err());
+    }
+}
@@ -0,0 +1,481 @@
+use crate::node::{color_code, NodeId, MAX_COLOR, NO_COLOR};
+use crate::{ColumnChooser, Observer, Solver, SolverError, SolverState};
+
+use std::ops::RangeInclusive;
+
+impl SolverState {
+    /// Returns the index of the column with the given id, giving it one if needed.
+    /// Ids stay dense as long as that takes no more memory than compacting them would.
+    fn column_index(&mut self, column: usize) -> usize {
+        if let Some(col_idx) = self.find_column(column) {
+            return col_idx;
+        }
+
+        let column_count = if self.column_ids.is_empty() && column < 2 * self.nodes.len() + 64 {
+            column + 1
+        } else {
+            if self.column_ids.is_empty() {
+                self.column_ids = (0..self.column_sizes.len()).collect();
+                self.column_indices = self.column_ids.iter().map(|id| (*id, *id)).collect();
+            }
+            self.column_indices.insert(column, self.column_ids.len());
+            self.column_ids.push(column);
+            self.column_ids.len()
+        };
+
+        self.column_sizes.resize(column_count, 0);
+        self.column_bounds.resize(column_count, 1);
+        self.column_limits.resize(column_count, 1);
+        self.column_slacks.resize(column_count, 0);
+        self.column_headers.resize(column_count, NodeId::invalid());
+
+        column_count - 1
+    }
+
+    /// Returns the header of the column, adding an empty one if the column has none.
+    /// Primary columns are linked to the header ring in ascending order of their ids.
+    fn column_header(&mut self, col_idx: usize, secondary: bool) -> NodeId {
+        let header_id = self.column_headers[col_idx];
+        if header_id.is_valid() {
+            return header_id;
+        }
+
+        let header_id = self.nodes.push(None, col_idx, NO_COLOR);
+
+        if !secondary {
+            let column = self.column_id(col_idx);
+
+            let mut right_id = self.nodes.right[self.header];
+            while right_id != self.header && self.column_id(self.nodes.col(right_id)) < column {
+                right_id = self.nodes.right[right_id];
+            }
+
+            let left_id = self.nodes.left[right_id];
+            self.link_horizontal(left_id, header_id);
+            self.link_horizontal(header_id, right_id);
+        }
+
+        self.column_headers[col_idx] = header_id;
+        header_id
+    }
+
+    /// Checks a row with sorted columns the way the builder checks its rows
+    fn validate_row(
+        &self,
+        row_idx: usize,
+        row: &[(usize, Option<usize>)],
+    ) -> Result<(), SolverError> {
+        if row.is_empty() {
+            return Err(SolverError::EmptyRow { row: row_idx });
+        }
+
+        for pair in row.windows(2) {
+            if pair[0].0 == pair[1].0 {
+                return Err(SolverError::DuplicateColumn {
+                    row: row_idx,
+                    column: pair[0].0,
+                });
+            }
+        }
+
+        for (column, color) in row {
+            // Secondary columns are not in the header ring
+            let secondary = self
+                .find_column(*column)
+                .map(|col_idx| self.column_headers[col_idx])
+                .is_some_and(|header_id| {
+                    header_id.is_valid() && self.nodes.left[header_id] == header_id
+                });
+
+            if color.is_some() && !secondary {
+                return Err(SolverError::ColoredPrimaryColumn {
+                    row: row_idx,
+                    column: *column,
+                });
+            }
+
+            if color.is_some_and(|color| color > MAX_COLOR) {
+                return Err(SolverError::ColorTooLarge {
+                    row: row_idx,
+                    column: *column,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Editing the problem of an existing solver. Any search in progress is discarded,
+/// and the search starts again from the beginning after each change.
+///
+/// Rows keep their indices: new rows are added after the existing ones, and removed rows
+/// are left empty. Columns keep existing until they are removed, even without any rows.
+impl<C: ColumnChooser, O: Observer> Solver<C, O> {
+    /// Adds a row and returns its index. Columns that do not exist yet are added
+    /// as primary columns. Returns an error and leaves the problem as it is if the row
+    /// is empty or contains a column more than once.
+    pub fn add_row(&mut self, row: Vec<usize>) -> Result<usize, SolverError> {
+        self.add_colored_row(row.into_iter().map(|col| (col, None)).collect())
+    }
+
+    /// Adds a row whose columns may be assigned a color and returns its index.
+    /// Only existing secondary columns can be assigned a color.
+    /// See [`SolverBuilder::add_colored_row`](crate::SolverBuilder::add_colored_row).
+    pub fn add_colored_row(
+        &mut self,
+        mut row: Vec<(usize, Option<usize>)>,
+    ) -> Result<usize, SolverError> {
+        row.sort_unstable_by_key(|(col, _)| *col);
+
+        let row_idx = self.state.row_nodes.len();
+        self.state.validate_row(row_idx, &row)?;
+
+        self.unwind();
+        let mut first = NodeId::invalid();
+        let mut prev = NodeId::invalid();
+
+        for (column, color) in row {
+            let col_idx = self.state.column_index(column);
+            let header_id = self.state.column_header(col_idx, false);
+
+            let node_id = self
+                .state
+                .nodes
+                .push(Some(row_idx), col_idx, color_code(color));
+            self.state
+                .link_vertical(self.state.nodes.up[header_id], node_id);
+            self.state.link_vertical(node_id, header_id);
+            self.state.column_sizes[col_idx] += 1;
+
+            if prev.is_valid() {
+                self.state.link_horizontal(prev, node_id);
+            } else {
+                first = node_id;
+            }
+            prev = node_id;
+        }
+
+        if first.is_valid() {
+            self.state.link_horizontal(prev, first);
+        }
+        self.state.row_nodes.push(first);
+
+        self.prepare();
+        Ok(row_idx)
+    }
+
+    /// Removes the row, leaving an empty row in its place
+    pub fn remove_row(&mut self, row: usize) {
+        self.unwind();
+
+        let first = self.state.row_nodes.get(row).copied().unwrap_or_default();
+        if !first.is_valid() {
+            self.prepare();
+            return;
+        }
+
+        let mut current_id = first;
+        loop {
+            self.state.detach_node(current_id);
+
+            current_id = self.state.nodes.right[current_id];
+            if current_id == first {
+                break;
+            }
+        }
+
+        self.state.row_nodes[row] = NodeId::invalid();
+        self.initial.rows.retain(|row_idx| *row_idx != row);
+        self.initial.assumptions.retain(|row_idx| *row_idx != row);
+
+        self.prepare();
+    }
+
+    /// Adds a primary column, which no row contains yet, if the column does not exist
+    pub fn add_column(&mut self, column: usize) {
+        self.unwind();
+
+        let col_idx = self.state.column_index(column);
+        self.state.column_header(col_idx, false);
+
+        self.prepare();
+    }
+
+    /// Adds a secondary column, which no row contains yet, if the column does not exist.
+    /// Rows added after this may cover the column at most once.
+    pub fn add_secondary_column(&mut self, column: usize) {
+        self.unwind();
+
+        let col_idx = self.state.column_index(column);
+        self.state.column_header(col_idx, true);
+
+        self.prepare();
+    }
+
+    /// Sets how many times the column must be covered, adding it as a primary column
+    /// if it does not exist. See [`SolverBuilder::set_column_bounds`](crate::SolverBuilder::set_column_bounds).
+    /// Returns an error and leaves the problem as it is if the bounds are invalid.
+    pub fn set_column_bounds(
+        &mut self,
+        column: usize,
+        bounds: RangeInclusive<usize>,
+    ) -> Result<(), SolverError> {
+        if *bounds.end() == 0 || bounds.start() > bounds.end() {
+            return Err(SolverError::InvalidColumnBounds { column });
+        }
+
+        self.unwind();
+
+        let col_idx = self.state.column_index(column);
+        self.state.column_header(col_idx, false);
+        self.state.column_bounds[col_idx] = *bounds.end();
+        self.state.column_limits[col_idx] = *bounds.end();
+        self.state.column_slacks[col_idx] = bounds.end() - bounds.start();
+
+        self.prepare();
+        Ok(())
+    }
+
+    /// Removes the column from the problem and from every row that contains it.
+    /// Rows that contain no other columns are left empty.
+    pub fn remove_column(&mut self, column: usize) {
+        self.unwind();
+
+        let Some(col_idx) = self.state.find_column(column) else {
+            self.prepare();
+            return;
+        };
+        let header_id = self.state.column_headers[col_idx];
+        if !header_id.is_valid() {
+            self.prepare();
+            return;
+        }
+
+        let mut current_id = self.state.nodes.down[header_id];
+        while current_id != header_id {
+            let left_id = self.state.nodes.left[current_id];
+            let right_id = self.state.nodes.right[current_id];
+            let row_idx = self.state.nodes.row(current_id).unwrap();
+
+            if right_id == current_id {
+                self.state.row_nodes[row_idx] = NodeId::invalid();
+                self.initial.rows.retain(|row| *row != row_idx);
+                self.initial.assumptions.retain(|row| *row != row_idx);
+            } else {
+                self.state.link_horizontal(left_id, right_id);
+                if self.state.row_nodes[row_idx] == current_id {
+                    self.state.row_nodes[row_idx] = right_id;
+                }
+            }
+
+            current_id = self.state.nodes.down[current_id];
+        }
+
+        // Secondary columns are not in the header ring
+        if self.state.nodes.left[header_id] != header_id {
+            self.state.detach_column(header_id);
+        }
+
+        self.state.column_headers[col_idx] = NodeId::invalid();
+        self.state.column_sizes[col_idx] = 0;
+        self.state.column_bounds[col_idx] = 1;
+        self.state.column_limits[col_idx] = 1;
+        self.state.column_slacks[col_idx] = 0;
+        self.initial.columns.retain(|col| *col != column);
+
+        self.prepare();
+    }
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod tests {
+    use crate::rng::Rng;
+    use crate::tests::{n_queens, random_colored_row, sorted};
+    use crate::{Solver, SolverBuilder, SolverError};
+
+    use std::ops::RangeInclusive;
+
+    #[test]
+    fn test_restart() {
+        let mut solver = n_queens(6).build();
+        let expected = solver.clone().collect::<Vec<_>>();
+
+        assert_eq!(2, solver.count_solutions_up_to(2));
+        solver.restart();
+        assert_eq!(expected, solver.clone().collect::<Vec<_>>());
+
+        assert_eq!(Ok(4), solver.count_solutions());
+        solver.restart();
+        assert_eq!(expected, solver.collect::<Vec<_>>());
+
+        let mut builder = n_queens(6);
+        builder.set_initial_rows(vec![1]);
+        builder.set_initial_columns(vec![12]);
+
+        let mut solver = builder.build();
+        let expected = solver.clone().collect::<Vec<_>>();
+        assert_eq!(1, expected.len());
+
+        solver.next();
+        solver.remove_row(35);
+        assert_eq!(expected, solver.clone().collect::<Vec<_>>());
+
+        // Every other solution is left, as none of them use the corner
+        // whose diagonal is covered initially
+        solver.remove_row(1);
+        assert_eq!(Ok(3), solver.count_solutions());
+    }
+
+    #[test]
+    fn test_add_and_remove_columns() {
+        let mut solver = n_queens(4).build();
+
+        // Without the diagonals, the queens are rooks
+        for col in 8..28 {
+            solver.remove_column(col);
+        }
+        assert_eq!(Ok(24), solver.clone().count_solutions());
+
+        solver.add_column(100);
+        assert_eq!(Ok(0), solver.clone().count_solutions());
+
+        let row = solver.add_row(vec![100]).unwrap();
+        assert_eq!(16, row);
+        assert!(solver.clone().all(|solution| solution.contains(&16)));
+        assert_eq!(Ok(24), solver.clone().count_solutions());
+
+        solver.add_secondary_column(200);
+        solver.add_row(vec![0, 4, 200]).unwrap();
+        solver.add_row(vec![1, 5, 100, 200]).unwrap();
+        assert_eq!(Ok(30 + 6), solver.clone().count_solutions());
+
+        solver.remove_column(100);
+        solver.remove_row(18);
+        assert_eq!(Ok(30), solver.clone().count_solutions());
+
+        // Only the files are left, and two rows cover the first one
+        for col in (0..4).chain([200]) {
+            solver.remove_column(col);
+        }
+        assert_eq!(Ok(5 * 4 * 4 * 4), solver.count_solutions());
+    }
+
+    #[test]
+    fn test_sparse_columns() {
+        let mut solver = Solver::new(vec![vec![0, 4_000_000_000], vec![4_000_000_000]], vec![]);
+        assert_eq!(2, solver.state.column_sizes.len());
+
+        solver.add_row(vec![0, 5_000_000_000]).unwrap();
+        solver.add_row(vec![5_000_000_000]).unwrap();
+        assert_eq!(3, solver.state.column_sizes.len());
+        assert_eq!(vec![vec![0, 3], vec![1, 2]], sorted(solver.clone()));
+
+        solver.remove_column(4_000_000_000);
+        assert_eq!(vec![vec![0, 3], vec![2]], sorted(solver.clone()));
+
+        solver.add_row(vec![usize::MAX]).unwrap();
+        solver.add_row(vec![5_000_000_000, usize::MAX]).unwrap();
+        assert_eq!(vec![vec![0, 3, 4], vec![0, 5], vec![2, 4]], sorted(solver));
+    }
+
+    #[test]
+    fn test_invalid_edits() {
+        let mut solver = Solver::with_colors(vec![vec![(0, None), (1, Some(0))]], vec![1]);
+        solver.next();
+
+        assert_eq!(Err(SolverError::EmptyRow { row: 1 }), solver.add_row(vec![]));
+        assert_eq!(Err(SolverError::DuplicateColumn { row: 1, column: 2 }), solver.add_row(vec![2, 0, 2]));
+        assert_eq!(Err(SolverError::ColoredPrimaryColumn { row: 1, column: 0 }), solver.add_colored_row(vec![(0, Some(0))]));
+        assert_eq!(Err(SolverError::ColoredPrimaryColumn { row: 1, column: 2 }), solver.add_colored_row(vec![(2, Some(0))]));
+        assert_eq!(Err(SolverError::ColorTooLarge { row: 1, column: 1 }), solver.add_colored_row(vec![(1, Some(1 << 31))]));
+        assert_eq!(Err(SolverError::InvalidColumnBounds { column: 0 }), solver.set_column_bounds(0, 0..=0));
+        assert_eq!(Err(SolverError::InvalidColumnBounds { column: 0 }), solver.set_column_bounds(0, RangeInclusive::new(3, 1)));
+
+        // The search goes on where it was
+        assert_eq!(None, solver.next());
+        assert_eq!(Ok(1), solver.add_colored_row(vec![(1, Some(0)), (0, None)]));
+        assert_eq!(Ok(2), solver.count_solutions());
+    }
+
+    #[test]
+    fn test_against_rebuilding() {
+        let mut rng = Rng::new(16);
+
+        for iteration in 0..500 {
+            let primary_count = 1 + rng.below(3);
+            let column_count = primary_count + rng.below(3);
+            let random_row = |rng: &mut Rng| random_colored_row(rng, primary_count, column_count);
+
+            let mut builder = SolverBuilder::new();
+            builder.set_secondary_columns((primary_count..column_count).collect());
+            if rng.below(2) == 0 {
+                builder.set_column_bounds(0, rng.below(2)..=2);
+            }
+            if primary_count > 1 && rng.below(3) == 0 {
+                builder.set_initial_columns(vec![primary_count - 1]);
+            }
+            for _ in 0..1 + rng.below(8) {
+                builder.add_colored_row(random_row(&mut rng));
+            }
+
+            let mut used = vec![false; column_count];
+            for (col, _) in builder.rows.iter().flatten() {
+                used[*col] = true;
+            }
+
+            let mut solver = if iteration % 2 == 1 {
+                builder.clone().build_randomized(iteration)
+                    .with_chooser(crate::Mrv)
+            } else {
+                builder.clone().build()
+            };
+
+            // Columns without rows only exist in the solver once they are added
+            for col in primary_count..column_count {
+                solver.add_secondary_column(col);
+            }
+            if let Some(bounds) = builder.column_bounds.get(&0) {
+                solver.set_column_bounds(0, bounds.clone()).unwrap();
+                used[0] = true;
+            }
+
+            for _ in 0..4 {
+                solver.count_solutions_up_to(rng.below(3) as u128);
+                for _ in 0..rng.below(20) {
+                    solver.step();
+                }
+
+                if rng.below(2) == 0 && !builder.rows.is_empty() {
+                    let row_idx = rng.below(builder.rows.len());
+                    builder.rows[row_idx].clear();
+                    solver.remove_row(row_idx);
+                } else {
+                    let row = random_row(&mut rng);
+                    for (col, _) in &row {
+                        used[*col] = true;
+                    }
+                    builder.add_colored_row(row.clone());
+                    solver.add_colored_row(row).unwrap();
+                }
+
+                // The solver keeps primary columns that have lost all of their rows
+                let uncoverable = (0..primary_count).any(|col| {
+                    let required = builder.column_bounds.get(&col).map_or(1, |bounds| *bounds.start());
+                    used[col] && required > 0
+                        && !builder.initial_columns.contains(&col)
+                        && !builder.rows.iter().flatten().any(|(c, _)| *c == col)
+                });
+                // Without any primary columns to branch on, a new solver would not search at all
+                if !builder.rows.iter().flatten().any(|(c, _)| *c < primary_count && !builder.initial_columns.contains(c)) {
+                    continue;
+                }
+
+                let expected = if uncoverable { vec![] } else { sorted(builder.clone().build()) };
+
+                assert_eq!(expected, sorted(solver.clone()), "iteration {}: {:?}", iteration, builder);
+            }
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use crate::rng::Rng;
+use crate::{ColumnChooser, Observer, Solver, Step};
+
+/// Estimate of the size of a search, made by [`Solver::estimate_tree_size`]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TreeEstimate {
+    /// Number of random descents the estimate is based on
+    pub probes: u64,
+    /// Estimated number of rows the search tries, comparable to
+    /// [`SearchStats::nodes`](crate::SearchStats::nodes)
+    pub nodes: f64,
+    /// Estimated number of solutions
+    pub solutions: f64,
+    /// Estimated number of times a node is removed from its column, comparable to
+    /// [`SearchStats::updates`](crate::SearchStats::updates). The running time of
+    /// the search is roughly proportional to it.
+    pub updates: f64,
+    /// Standard error of the estimated number of nodes. With enough probes, the true number
+    /// is within two standard errors with a probability of about 95%. Trees with a few
+    /// large subtrees that the probes rarely reach make it an underestimate.
+    pub nodes_error: f64,
+    /// Standard error of the estimated number of solutions
+    pub solutions_error: f64,
+}
+
+/// Mean and standard error of the estimates of the probes
+#[derive(Debug, Default, Copy, Clone)]
+struct Samples {
+    count: u64,
+    sum: f64,
+    sum_of_squares: f64,
+}
+
+impl Samples {
+    fn add(&mut self, value: f64) {
+        self.count += 1;
+        self.sum += value;
+        self.sum_of_squares += value * value;
+    }
+
+    fn mean(&self) -> f64 {
+        match self.count {
+            0 => 0.0,
+            count => self.sum / count as f64,
+        }
+    }
+
+    fn standard_error(&self) -> f64 {
+        if self.count < 2 {
+            return f64::INFINITY;
+        }
+
+        let count = self.count as f64;
+        let variance = (self.sum_of_squares - self.sum * self.mean()) / (count - 1.0);
+
+        (variance.max(0.0) / count).sqrt()
+    }
+}
+
+impl<C: ColumnChooser, O: Observer> Solver<C, O> {
+    /// Estimates the size of the whole search with Knuth's random-probe method.
+    ///
+    /// Each probe descends from the root of the search to a leaf, choosing the columns
+    /// the way the search would and a random row of each. The number of nodes on each
+    /// level is estimated as the product of the numbers of rows on the levels above it.
+    /// The estimates are unbiased, so their averages converge to the true sizes, but for
+    /// unbalanced trees that can take many probes.
+    ///
+    /// The search is restarted afterwards, and its statistics are left as they were.
+    ///
+    /// ```
+    /// use algx::Solver;
+    ///
+    /// // Each of the three columns can be covered by either of its two rows
+    /// let rows = vec![vec![0], vec![0], vec![1], vec![1], vec![2], vec![2]];
+    /// let estimate = Solver::new(rows, vec![]).estimate_tree_size(10, 1);
+    ///
+    /// assert_eq!(8.0, estimate.solutions);
+    /// assert_eq!(2.0 + 4.0 + 8.0, estimate.nodes);
+    /// assert_eq!(0.0, estimate.nodes_error);
+    /// ```
+    pub fn estimate_tree_size(&mut self, probes: u64, seed: u64) -> TreeEstimate {
+        let stats = self.state.stats.clone();
+        let search_rng = self.rng;
+
+        let mut rng = Rng::new(seed);
+        let mut nodes = Samples::default();
+        let mut solutions = Samples::default();
+        let mut updates = Samples::default();
+
+        for _ in 0..probes {
+            self.unwind();
+
+            let probe = self.probe(&mut rng);
+            nodes.add(probe.nodes);
+            solutions.add(probe.solutions);
+            updates.add(probe.updates);
+        }
+
+        self.unwind();
+        self.rng = search_rng;
+        self.prepare();
+        self.state.stats = stats;
+
+        TreeEstimate {
+            probes,
+            nodes: nodes.mean(),
+            solutions: solutions.mean(),
+            updates: updates.mean(),
+            nodes_error: nodes.standard_error(),
+            solutions_error: solutions.standard_error(),
+        }
+    }
+
+    /// Descends from the root of the unwound search to a leaf through random rows
+    fn probe(&mut self, rng: &mut Rng) -> Probe {
+        let mut probe = Probe::default();
+
+        if !self.select_initial() {
+            return probe;
+        }
+
+        // Number of nodes on the current level, if every node had as many rows
+        // to choose from as the ones chosen by the probe
+        let mut weight = 1.0;
+
+        loop {
+            let Some(column_id) = self.choose_column() else {
+                // Like the search, initial rows that leave no columns are a solution only
+                // if there are any
+                if self.depth > 0 || !self.initial.selected_rows.is_empty() {
+                    probe.solutions = weight;
+                }
+                return probe;
+            };
+
+            // Every row is tried first to count them, which is the work the search
+            // does on the node
+            let updates = self.state.stats.updates;
+            let shuffle_rng = self.rng;
+
+            self.branch_on(column_id);
+            let rows = self.skip_rows(usize::MAX);
+
+            probe.updates += weight * (self.state.stats.updates - updates) as f64;
+
+            if rows == 0 {
+                return probe;
+            }
+
+            weight *= rows as f64;
+            probe.nodes += weight;
+
+            if let Some(Step::Restore(first_id)) = self.step_stack.pop() {
+                self.restore(first_id);
+            }
+
+            self.rng = shuffle_rng;
+            self.branch_on(column_id);
+            self.skip_rows(rng.below(rows));
+
+            if let Some(Step::Forward(node_id)) = self.step_stack.pop() {
+                self.enter(node_id);
+            }
+        }
+    }
+
+    /// Tries and backtracks from up to the given number of rows on the current level,
+    /// returning how many of them could be tried. Rows that can not lead to a solution
+    /// are passed over without counting them.
+    fn skip_rows(&mut self, count: usize) -> usize {
+        let mut skipped = 0;
+
+        while skipped < count {
+            let Some(Step::Forward(node_id)) = self.step_stack.last().copied() else {
+                break;
+            };
+
+            self.step_stack.pop();
+            if self.enter(node_id) {
+                self.step_stack.pop();
+                self.step_backward(node_id);
+                skipped += 1;
+            }
+        }
+
+        skipped
+    }
+}
+
+/// Estimates made by a single probe
+#[derive(Debug, Default, Copy, Clone)]
+struct Probe {
+    nodes: f64,
+    solutions: f64,
+    updates: f64,
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod tests {
+    use crate::tests::n_queens;
+    use crate::SolverBuilder;
+
+    #[test]
+    fn test_n_queens_estimate() {
+        let mut solver = n_queens(8).build();
+        let estimate = solver.estimate_tree_size(2000, 21);
+
+        assert_eq!(Ok(92), solver.count_solutions());
+        let nodes = solver.stats().nodes() as f64;
+        let updates = solver.stats().updates as f64;
+
+        assert_eq!(2000, estimate.probes);
+        assert!((estimate.solutions - 92.0).abs() < 3.0 * estimate.solutions_error, "{:?}", estimate);
+        assert!((estimate.nodes - nodes).abs() < 3.0 * estimate.nodes_error, "{:?}", estimate);
+        assert!((estimate.updates - updates).abs() < 0.2 * updates, "{:?} {}", estimate, updates);
+    }
+
+    #[test]
+    fn test_bounds_and_shuffling() {
+        let mut builder = SolverBuilder::new();
+        builder.set_rows(vec![vec![0, 1], vec![0, 2], vec![0], vec![1], vec![1, 2], vec![2, 3], vec![3]]);
+        builder.set_column_bounds(0, 1..=2);
+        builder.set_column_bounds(3, 0..=1);
+        builder.set_seed(21);
+
+        let mut solver = builder.build();
+        let estimate = solver.estimate_tree_size(4000, 21);
+
+        let solutions = solver.count_solutions().unwrap() as f64;
+        let nodes = solver.stats().nodes() as f64;
+
+        assert!((estimate.solutions - solutions).abs() < 0.1 * solutions, "{:?} {}", estimate, solutions);
+        assert!((estimate.nodes - nodes).abs() < 0.1 * nodes, "{:?} {}", estimate, nodes);
+    }
+
+    #[test]
+    fn test_restarts_search() {
+        let mut solver = n_queens(6).build();
+        solver.next();
+        let stats = solver.stats().clone();
+
+        solver.estimate_tree_size(10, 21);
+
+        assert_eq!(&stats, solver.stats());
+        assert_eq!(Ok(4), solver.count_solutions());
+    }
+
+    #[test]
+    fn test_no_solutions() {
+        let mut builder = n_queens(4);
+        builder.set_initial_rows(vec![0, 5]);
+
+        let estimate = builder.build().estimate_tree_size(10, 21);
+        assert_eq!((0.0, 0.0, 0.0), (estimate.nodes, estimate.solutions, estimate.solutions_error));
+
+        let estimate = n_queens(3).build().estimate_tree_size(10, 21);
+        assert_eq!((0.0, 0.0), (estimate.solutions, estimate.solutions_error));
+        assert!(estimate.nodes > 0.0);
+    }
+}
@@ -0,0 +1,322 @@
+use crate::node::NodeId;
+use crate::{ColumnChooser, Observer, Solver, SolverState, Step};
+
+use std::borrow::Cow;
+use std::fmt::Write;
+
+impl<C: ColumnChooser, O: Observer> Solver<C, O> {
+    /// Draws the rows and columns still left in the search as a grid, with the id of each
+    /// row on the left and the id of each column on top. Secondary columns are shown as
+    /// long as one of the rows left contains them, and the column chosen for the next step
+    /// is shown with its rows until one of them is added to the solution.
+    ///
+    /// ```
+    /// use algx::Solver;
+    ///
+    /// let mut solver = Solver::new(vec![vec![0, 1], vec![0], vec![1], vec![1, 2], vec![2]], vec![]);
+    /// assert_eq!(
+    ///     "   0  1  2\n\
+    ///      0 [x, x, -]\n\
+    ///      1 [x, -, -]\n\
+    ///      2 [-, x, -]\n\
+    ///      3 [-, x, x]\n\
+    ///      4 [-, -, x]\n",
+    ///     solver.matrix_to_ascii(),
+    /// );
+    ///
+    /// // Row 0 covers columns 0 and 1, which hides the other rows that contain them
+    /// solver.step();
+    /// assert_eq!(vec![0], solver.partial_solution());
+    /// assert_eq!("   2\n4 [x]\n", solver.matrix_to_ascii());
+    /// ```
+    pub fn matrix_to_ascii(&self) -> String {
+        let state = self.matrix_state();
+        let (columns, rows) = state.active_matrix();
+
+        let column_ids = columns
+            .iter()
+            .map(|col| state.column_id(*col).to_string())
+            .collect::<Vec<_>>();
+        let width = column_ids.iter().map(String::len).max().unwrap_or(1);
+        let row_width = rows
+            .iter()
+            .map(|row| row.to_string().len())
+            .max()
+            .unwrap_or(0);
+
+        let mut ascii = format!("{:row_width$}  ", "");
+        for (i, id) in column_ids.iter().enumerate() {
+            let separator = if i == 0 { "" } else { "  " };
+            write!(ascii, "{separator}{id:>width$}").unwrap();
+        }
+        ascii.truncate(ascii.trim_end().len());
+        ascii.push('\n');
+
+        for row in rows {
+            let mut cells = vec!["-"; columns.len()];
+            for node_id in state.row_node_ids(row) {
+                let col = state.nodes.col(node_id);
+                if let Ok(i) = columns.binary_search(&col) {
+                    cells[i] = "x";
+                }
+            }
+
+            write!(ascii, "{row:>row_width$} [").unwrap();
+            for (i, cell) in cells.iter().enumerate() {
+                let separator = if i == 0 { "" } else { ", " };
+                write!(ascii, "{separator}{cell:>width$}").unwrap();
+            }
+            ascii.push_str("]\n");
+        }
+
+        ascii
+    }
+
+    /// Describes the links between the nodes of the rows and columns still left in the search
+    /// in the DOT language of Graphviz. Each node links to the node on its right and the node
+    /// below it, wrapping around to the first one. The nodes are pinned to a grid, so the
+    /// graph is meant to be drawn with `neato`.
+    pub fn matrix_to_dot(&self) -> String {
+        let state = self.matrix_state();
+        let (columns, rows) = state.active_matrix();
+        let root_id = state.header;
+
+        let mut dot = String::from("digraph dlx {\n");
+        dot.push_str("    layout=neato;\n");
+        dot.push_str("    node [shape=box, width=0.6, height=0.4];\n");
+        dot.push_str("    edge [arrowsize=0.5];\n\n");
+
+        let mut links = vec![];
+        let mut shown = vec![root_id];
+
+        writeln!(
+            dot,
+            "    n{} [label=\"root\", pos=\"0,0!\"];",
+            root_id.value()
+        )
+        .unwrap();
+        links.push((root_id, state.nodes.right[root_id], "right"));
+
+        for (x, col) in columns.iter().copied().enumerate() {
+            let header_id = state.column_headers[col];
+
+            let label = format!("{} ({})", state.column_id(col), state.column_sizes[col]);
+            let style = if state.nodes.left[header_id] == header_id {
+                ", style=dashed"
+            } else {
+                ""
+            };
+            writeln!(
+                dot,
+                "    n{} [label=\"{label}\", pos=\"{},0!\"{style}];",
+                header_id.value(),
+                x + 1,
+            )
+            .unwrap();
+            shown.push(header_id);
+
+            if state.nodes.right[header_id] != header_id {
+                links.push((header_id, state.nodes.right[header_id], "right"));
+            }
+            links.push((header_id, state.nodes.down[header_id], "down"));
+        }
+
+        for (y, row) in rows.iter().copied().enumerate() {
+            for node_id in state.row_node_ids(row) {
+                let Ok(x) = columns.binary_search(&state.nodes.col(node_id)) else {
+                    continue;
+                };
+
+                writeln!(
+                    dot,
+                    "    n{} [label=\"{row}\", pos=\"{},{}!\"];",
+                    node_id.value(),
+                    x + 1,
+                    -(y as isize) - 1,
+                )
+                .unwrap();
+                shown.push(node_id);
+
+                if state.nodes.right[node_id] != node_id {
+                    links.push((node_id, state.nodes.right[node_id], "right"));
+                }
+                links.push((node_id, state.nodes.down[node_id], "down"));
+            }
+        }
+
+        dot.push('\n');
+
+        // Secondary columns can still link to rows that are not shown
+        for (from, to, direction) in links {
+            if !shown.contains(&to) {
+                continue;
+            }
+
+            let color = if direction == "right" { "blue" } else { "red" };
+            writeln!(
+                dot,
+                "    n{} -> n{} [color={color}];",
+                from.value(),
+                to.value()
+            )
+            .unwrap();
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Returns the state of the matrix with the column chosen for the next step uncovered,
+    /// so that its rows are shown until one of them is added to the solution
+    fn matrix_state(&self) -> Cow<'_, SolverState> {
+        let Some(Step::Forward(node_id)) = self.step_stack.last().copied() else {
+            return Cow::Borrowed(&self.state);
+        };
+
+        let column_id = self.state.header_of(node_id);
+        let col = self.state.nodes.col(column_id);
+        if self.state.column_bounds[col] != 0 {
+            return Cow::Borrowed(&self.state);
+        }
+
+        let mut state = self.state.clone();
+
+        let mut up_id = state.nodes.up[column_id];
+        while up_id != column_id {
+            state.attach_row(up_id);
+            up_id = state.nodes.up[up_id];
+        }
+        state.attach_column(column_id);
+
+        Cow::Owned(state)
+    }
+}
+
+impl SolverState {
+    /// Returns the indices of the columns and rows still left in the search, in order.
+    /// A row is left if none of its nodes have been removed from their columns and
+    /// its columns are left.
+    fn active_matrix(&self) -> (Vec<usize>, Vec<usize>) {
+        let root_id = self.header;
+        let mut columns = vec![];
+
+        let mut header_id = self.nodes.right[root_id];
+        while header_id != root_id {
+            columns.push(self.nodes.col(header_id));
+            header_id = self.nodes.right[header_id];
+        }
+
+        let mut rows = vec![];
+        let mut secondary_columns = vec![];
+
+        for row in 0..self.row_nodes.len() {
+            let node_ids = self.row_node_ids(row);
+            if node_ids.is_empty() || !node_ids.iter().all(|id| self.is_attached(*id)) {
+                continue;
+            }
+
+            let mut row_secondary_columns = vec![];
+            let mut is_active = true;
+
+            for node_id in node_ids {
+                let col = self.nodes.col(node_id);
+                let header_id = self.column_headers[col];
+
+                if self.nodes.left[header_id] == header_id {
+                    row_secondary_columns.push(col);
+                } else if !columns.contains(&col) {
+                    is_active = false;
+                }
+            }
+
+            if is_active {
+                rows.push(row);
+                secondary_columns.append(&mut row_secondary_columns);
+            }
+        }
+
+        columns.append(&mut secondary_columns);
+        columns.sort_unstable();
+        columns.dedup();
+
+        (columns, rows)
+    }
+
+    /// Returns whether the node is linked to from the nodes above and below it
+    fn is_attached(&self, node_id: NodeId) -> bool {
+        self.nodes.down[self.nodes.up[node_id]] == node_id
+            && self.nodes.up[self.nodes.down[node_id]] == node_id
+    }
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod tests {
+    use crate::tests::n_queens;
+    use crate::{Solver, SolverBuilder};
+
+    #[test]
+    fn test_basic_solve() {
+        let mut solver = Solver::new(vec![
+            vec![0, 1],
+            vec![0, 2],
+            vec![1, 3],
+            vec![2, 3],
+            vec![0, 1, 2],
+            vec![1, 2, 3],
+        ], vec![0, 2]);
+
+        // Covering the initial columns leaves only the row that covers the other two
+        assert_eq!("   1  3\n2 [x, x]\n", solver.matrix_to_ascii());
+
+        solver.step();
+        assert_eq!("\n", solver.matrix_to_ascii());
+
+        solver.count_solutions().unwrap();
+        assert_eq!("   1  3\n2 [x, x]\n", solver.matrix_to_ascii());
+    }
+
+    #[test]
+    fn test_wide_ids() {
+        let mut builder = SolverBuilder::new();
+        builder.set_rows(vec![vec![5, 100], vec![5], vec![100, 2000]]);
+        builder.set_secondary_columns(vec![2000]);
+
+        assert_eq!(
+            "      5   100  2000\n\
+             0 [   x,    x,    -]\n\
+             1 [   x,    -,    -]\n\
+             2 [   -,    x,    x]\n",
+            builder.build().matrix_to_ascii(),
+        );
+    }
+
+    #[test]
+    fn test_secondary_columns() {
+        let mut builder = SolverBuilder::new();
+        builder.set_rows(vec![vec![0, 2], vec![1, 2], vec![0], vec![1], vec![3]]);
+        builder.set_secondary_columns(vec![2, 3]);
+        builder.set_initial_rows(vec![0]);
+
+        // Column 2 is covered by the initial row and column 3 only has a row that can not
+        // be in a solution
+        let solver = builder.build();
+        assert_eq!("   1  3\n3 [x, -]\n4 [-, x]\n", solver.matrix_to_ascii());
+    }
+
+    #[test]
+    fn test_dot_links() {
+        let mut solver = n_queens(4).build();
+        solver.step();
+
+        let dot = solver.matrix_to_dot();
+        assert!(dot.starts_with("digraph dlx {\n") && dot.ends_with("}\n"));
+
+        let nodes = dot.lines().filter(|line| line.contains("pos=")).count();
+        let links = dot.lines().filter(|line| line.contains("->")).count();
+
+        // Every node links down, and every node but the secondary headers links right
+        let secondary_headers = dot.lines().filter(|line| line.contains("dashed")).count();
+        assert_eq!(2 * nodes - 1 - secondary_headers, links);
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{ColumnChooser, Columns, Interruption, Observer, Solver, SolverBuilder, SolverError};
+
+/// Reason why a problem has no solutions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Infeasibility {
+    /// The initial columns, initial rows and assumptions can not be in the same solution
+    Selection(SolverError),
+    /// There are not enough rows left in the primary column to cover it as many times
+    /// as required, once the initial columns and rows are covered
+    UncoverableColumn { column: usize },
+    /// The problem has no solutions even when the rows are reduced to these columns.
+    /// Leaving out any one of the columns would make it solvable. The rows are those
+    /// that contain the columns.
+    Core {
+        columns: Vec<usize>,
+        rows: Vec<usize>,
+    },
+}
+
+impl<C: ColumnChooser, O: Observer> Solver<C, O> {
+    /// Explains why the problem has no solutions, or returns `None` if it has one.
+    ///
+    /// Conflicts between the initial columns and rows, and columns that can not be covered
+    /// after them, are found right away. Otherwise this searches for a minimal set of
+    /// columns that can not be covered, by leaving out one column at a time and checking
+    /// whether the rest still has no solutions. That takes a search for each column,
+    /// which only respects the cancellation token of this solver.
+    ///
+    /// The search is restarted afterwards.
+    pub fn explain_infeasibility(&mut self) -> Result<Option<Infeasibility>, Interruption> {
+        self.unwind();
+        let conflict = self.selection_conflict();
+        let builder = self.to_builder();
+
+        let uncoverable = self.select_initial().then(|| {
+            Columns::new(&self.state)
+                .find(|column| column.branches() == 0)
+                .map(|column| column.index())
+        });
+
+        self.unwind();
+        self.prepare();
+
+        if let Some(err) = conflict {
+            return Ok(Some(Infeasibility::Selection(err)));
+        }
+        if let Some(Some(column)) = uncoverable {
+            return Ok(Some(Infeasibility::UncoverableColumn { column }));
+        }
+
+        let solution = self.try_next();
+        self.restart();
+        if solution?.is_some() {
+            return Ok(None);
+        }
+
+        let mut columns = builder
+            .rows
+            .iter()
+            .flatten()
+            .map(|(column, _)| *column)
+            .filter(|column| !builder.initial_columns.contains(column))
+            .collect::<Vec<_>>();
+        columns.sort_unstable();
+        columns.dedup();
+
+        let mut i = 0;
+        while i < columns.len() {
+            let column = columns.remove(i);
+
+            if self.is_solvable_with(&builder, &columns)? {
+                columns.insert(i, column);
+                i += 1;
+            }
+        }
+
+        let rows = (0..builder.rows.len())
+            .filter(|row_idx| {
+                builder.rows[*row_idx]
+                    .iter()
+                    .any(|(column, _)| columns.binary_search(column).is_ok())
+            })
+            .collect();
+
+        Ok(Some(Infeasibility::Core { columns, rows }))
+    }
+
+    /// Checks whether the problem has a solution when its rows are reduced
+    /// to the given columns and the initial columns
+    fn is_solvable_with(
+        &self,
+        builder: &SolverBuilder,
+        columns: &[usize],
+    ) -> Result<bool, Interruption> {
+        let mut builder = builder.clone();
+
+        for row in &mut builder.rows {
+            row.retain(|(column, _)| {
+                columns.binary_search(column).is_ok() || builder.initial_columns.contains(column)
+            });
+        }
+
+        // Without any primary columns, choosing no rows is a solution
+        if columns
+            .iter()
+            .all(|column| builder.secondary_columns.contains(column))
+        {
+            return Ok(true);
+        }
+
+        let mut solver = builder.build();
+        if let Some(token) = &self.cancellation_token {
+            solver.set_cancellation_token(token.clone());
+        }
+
+        Ok(solver.try_next()?.is_some())
+    }
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod tests {
+    use crate::rng::Rng;
+    use crate::tests::n_queens;
+    use crate::{Infeasibility, Solver, SolverBuilder, SolverError};
+
+    #[test]
+    fn test_solvable() {
+        assert_eq!(Ok(None), n_queens(4).build().explain_infeasibility());
+    }
+
+    #[test]
+    fn test_selection() {
+        let mut builder = n_queens(4);
+        builder.set_initial_rows(vec![0, 5]);
+
+        assert_eq!(
+            Ok(Some(Infeasibility::Selection(SolverError::ConflictingRows { first: 0, second: 5 }))),
+            builder.build().explain_infeasibility()
+        );
+
+        let mut solver = Solver::new(vec![vec![0, 1], vec![1, 2]], vec![1]);
+        solver.push_assumption(0);
+
+        assert_eq!(
+            Ok(Some(Infeasibility::Selection(SolverError::ConflictingCoveredColumn { column: 1 }))),
+            solver.explain_infeasibility()
+        );
+        assert_eq!(&[0], solver.assumptions());
+    }
+
+    #[test]
+    fn test_uncoverable_column() {
+        // Both rows of the last column conflict with the initial row
+        let mut builder = SolverBuilder::new();
+        builder.set_rows(vec![vec![0, 1], vec![1, 2], vec![0, 2], vec![3]]);
+        builder.set_initial_rows(vec![0]);
+
+        assert_eq!(Ok(Some(Infeasibility::UncoverableColumn { column: 2 })), builder.build().explain_infeasibility());
+    }
+
+    #[test]
+    fn test_core() {
+        // Two queens can not be placed on a 2x2 board, and the rest of the problem is fine.
+        // One of the ranks is enough to show it.
+        let mut builder = SolverBuilder::new();
+        builder.set_rows(vec![
+            vec![0, 2, 10],
+            vec![0, 3, 11],
+            vec![1, 2, 11],
+            vec![1, 3, 10],
+            vec![4, 5],
+            vec![4, 6],
+            vec![5, 7],
+            vec![6, 7],
+        ]);
+        builder.set_secondary_columns(vec![10, 11]);
+
+        let mut solver = builder.build();
+        let infeasibility = solver.explain_infeasibility();
+
+        assert_eq!(Ok(Some(Infeasibility::Core { columns: vec![1, 2, 3, 10, 11], rows: vec![0, 1, 2, 3] })), infeasibility);
+        assert_eq!(Ok(0), solver.count_solutions());
+    }
+
+    #[test]
+    fn test_core_is_minimal() {
+        let mut rng = Rng::new(19);
+
+        for _ in 0..200 {
+            let mut builder = SolverBuilder::new();
+            builder.set_secondary_columns(vec![4, 5]);
+            for _ in 0..1 + rng.below(6) {
+                let mut row = (0..6).filter(|_| rng.below(3) == 0).collect::<Vec<_>>();
+                // Rows with only secondary columns would leave the problem without primary columns
+                if row.iter().all(|column| *column >= 4) {
+                    row.push(rng.below(4));
+                }
+                builder.add_row(row);
+            }
+
+            let Ok(Some(Infeasibility::Core { columns, .. })) = builder.clone().build().explain_infeasibility() else {
+                continue;
+            };
+
+            let solvable_with = |columns: &[usize]| {
+                let mut builder = builder.clone();
+                for row in &mut builder.rows {
+                    row.retain(|(column, _)| columns.contains(column));
+                }
+                columns.iter().all(|column| *column >= 4) || builder.build().next().is_some()
+            };
+
+            assert!(!solvable_with(&columns), "{:?}", builder);
+            for i in 0..columns.len() {
+                let mut fewer = columns.clone();
+                fewer.remove(i);
+                assert!(solvable_with(&fewer), "{:?}", builder);
+            }
+        }
+    }
+}
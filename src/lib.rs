@@ -1,290 +1,807 @@
 //! Implementation of [Knuth's Algorithm X](https://en.wikipedia.org/wiki/Knuth%27s_Algorithm_X)
 //! for solving the [exact cover](https://en.wikipedia.org/wiki/Exact_cover) problem.
 //!
+mod assumption;
+mod backbone;
+mod budget;
+mod builder;
+mod chooser;
+mod dlx;
+mod edit;
+mod estimate;
+mod export;
+mod infeasibility;
 mod node;
+mod observer;
+#[cfg(not(target_arch = "wasm32"))]
+mod parallel;
+mod problem;
+mod progress;
+mod result;
+mod rng;
+mod snapshot;
+mod stats;
 #[cfg(target_arch = "wasm32")]
 mod wasm;
 
-use node::{Node, NodeId};
-
-use std::collections::BTreeMap;
+pub use backbone::RowStatus;
+use budget::Limits;
+pub use budget::{CancellationToken, Interruption, SearchBudget};
+pub use builder::SolverBuilder;
+pub use chooser::{
+    Column, ColumnChooser, Columns, FirstColumn, Mrv, MrvPriority, MrvRandom, Sharp,
+};
+pub use dlx::DlxProblem;
+pub use estimate::TreeEstimate;
+pub use infeasibility::Infeasibility;
+use node::{color_code, NodeId, Nodes, NO_COLOR, PURIFIED};
+pub use observer::{NoObserver, Observer};
+#[cfg(not(target_arch = "wasm32"))]
+pub use parallel::{ParallelSolutions, ParallelSolver};
+pub use problem::{Problem, ProblemSolutions};
+pub use progress::{Progress, ProgressSolutions};
+pub use result::{ParseError, SnapshotError, SolverError};
+use rng::Rng;
+pub use stats::SearchStats;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 #[derive(Default, Debug, Clone)]
 struct SolverState {
-    nodes: Vec<Node>,
+    nodes: Nodes,
     header: NodeId,
     column_sizes: Vec<usize>,
+    /// How many more times each column may be covered
+    column_bounds: Vec<usize>,
+    /// Upper bound given to each column, from which its bound counts down during the search
+    column_limits: Vec<usize>,
+    /// Difference between the upper and lower bound of each column
+    column_slacks: Vec<usize>,
+    /// First node of each row
+    row_nodes: Vec<NodeId>,
+    /// Header node of each column
+    column_headers: Vec<NodeId>,
+    /// Original id of each column when the ids were too sparse to be used as indices,
+    /// or empty if they are used as they are
+    column_ids: Vec<usize>,
+    /// Index of each column by its original id, when the ids are not used as they are
+    column_indices: HashMap<usize, usize>,
+    stats: SearchStats,
 }
 
 impl SolverState {
-    fn new_node(&mut self) -> NodeId {
-        self.nodes.push(Node::default());
-        NodeId::new(self.nodes.len() - 1)
+    /// Returns the id given to the column in the input
+    fn column_id(&self, col_idx: usize) -> usize {
+        self.column_ids.get(col_idx).copied().unwrap_or(col_idx)
+    }
+
+    /// Returns the index of the column with the given id, if it has one
+    fn find_column(&self, column: usize) -> Option<usize> {
+        if self.column_ids.is_empty() {
+            (column < self.column_sizes.len()).then_some(column)
+        } else {
+            self.column_indices.get(&column).copied()
+        }
+    }
+
+    /// Returns the nodes of the row from left to right, or none if the row has been removed
+    fn row_node_ids(&self, row: usize) -> Vec<NodeId> {
+        let first = self.row_nodes[row];
+        let mut node_ids = vec![];
+
+        let mut node_id = first;
+        while node_id.is_valid() {
+            node_ids.push(node_id);
+
+            node_id = self.nodes.right[node_id];
+            if node_id == first {
+                break;
+            }
+        }
+
+        node_ids
+    }
+
+    /// Returns the header of the column of the node
+    fn header_of(&self, node_id: NodeId) -> NodeId {
+        self.column_headers[self.nodes.col(node_id)]
     }
 
     fn link_horizontal(&mut self, left_id: NodeId, right_id: NodeId) {
-        let left = self.node_mut(left_id);
-        left.right = right_id;
+        self.nodes.right[left_id] = right_id;
+        self.nodes.left[right_id] = left_id;
+    }
 
-        let right = self.node_mut(right_id);
-        right.left = left_id;
+    fn link_vertical(&mut self, up_id: NodeId, down_id: NodeId) {
+        self.nodes.down[up_id] = down_id;
+        self.nodes.up[down_id] = up_id;
     }
 
     fn detach_column(&mut self, node_id: NodeId) {
-        let node = self.node(node_id);
-        let header = self.node(node.header);
-
-        let header_left_id = header.left;
-        let header_right_id = header.right;
+        let header_id = self.header_of(node_id);
 
-        let header_left = self.node_mut(header_left_id);
-        header_left.right = header_right_id;
+        let header_left_id = self.nodes.left[header_id];
+        let header_right_id = self.nodes.right[header_id];
 
-        let header_right = self.node_mut(header_right_id);
-        header_right.left = header_left_id;
+        self.link_horizontal(header_left_id, header_right_id);
     }
 
     fn attach_column(&mut self, node_id: NodeId) {
-        let node = self.node_mut(node_id);
-        let header_id = node.header;
+        let header_id = self.header_of(node_id);
 
-        let header = self.node_mut(header_id);
-        let header_left_id = header.left;
-        let header_right_id = header.right;
+        let header_left_id = self.nodes.left[header_id];
+        let header_right_id = self.nodes.right[header_id];
 
-        let header_left = self.node_mut(header_left_id);
-        header_left.right = header_id;
-
-        let header_right = self.node_mut(header_right_id);
-        header_right.left = header_id;
+        self.nodes.right[header_left_id] = header_id;
+        self.nodes.left[header_right_id] = header_id;
     }
 
     fn detach_row(&mut self, node_id: NodeId) {
-        let mut current_id = self.node_mut(node_id).right;
-
-        loop {
-            if current_id == node_id {
-                break;
-            }
-
-            let current_node = self.node_mut(current_id);
-            let current_col_idx = current_node.col;
-            let current_down_id = current_node.down;
-            let current_up_id = current_node.up;
-            let current_right_id = current_node.right;
+        let mut current_id = self.nodes.right[node_id];
 
-            self.node_mut(current_up_id).down = current_down_id;
-            self.node_mut(current_down_id).up = current_up_id;
+        while current_id != node_id {
+            // Purified nodes stay in their column so that unpurify can restore their color
+            if self.nodes.color[current_id] >= 0 {
+                self.link_vertical(self.nodes.up[current_id], self.nodes.down[current_id]);
 
-            self.column_sizes[current_col_idx] -= 1;
+                self.column_sizes[self.nodes.col(current_id)] -= 1;
+                self.stats.updates += 1;
+            }
 
-            current_id = current_right_id;
+            current_id = self.nodes.right[current_id];
         }
     }
 
     fn attach_row(&mut self, node_id: NodeId) {
-        let mut current_id = self.node_mut(node_id).left;
-
-        loop {
-            if current_id == node_id {
-                break;
-            }
-
-            let current_node = self.node_mut(current_id);
-            let current_col_idx = current_node.col;
-            let current_down_id = current_node.down;
-            let current_left_id = current_node.left;
-            let current_up_id = current_node.up;
+        let mut current_id = self.nodes.left[node_id];
 
-            self.column_sizes[current_col_idx] += 1;
+        while current_id != node_id {
+            if self.nodes.color[current_id] >= 0 {
+                self.column_sizes[self.nodes.col(current_id)] += 1;
 
-            self.node_mut(current_down_id).up = current_id;
-            self.node_mut(current_up_id).down = current_id;
+                let current_up_id = self.nodes.up[current_id];
+                let current_down_id = self.nodes.down[current_id];
+                self.nodes.up[current_down_id] = current_id;
+                self.nodes.down[current_up_id] = current_id;
+            }
 
-            current_id = current_left_id;
+            current_id = self.nodes.left[current_id];
         }
     }
 
-    fn node_column_size(&self, id: NodeId) -> usize {
-        self.column_sizes[self.node(id).col]
-    }
+    fn detach_node(&mut self, node_id: NodeId) {
+        if self.nodes.color[node_id] >= 0 {
+            self.link_vertical(self.nodes.up[node_id], self.nodes.down[node_id]);
 
-    fn node(&self, id: NodeId) -> &Node {
-        &self.nodes[id.value()]
+            self.column_sizes[self.nodes.col(node_id)] -= 1;
+            self.stats.updates += 1;
+        }
     }
 
-    fn node_mut(&mut self, id: NodeId) -> &mut Node {
-        &mut self.nodes[id.value()]
+    fn attach_node(&mut self, node_id: NodeId) {
+        if self.nodes.color[node_id] >= 0 {
+            self.column_sizes[self.nodes.col(node_id)] += 1;
+
+            let node_up_id = self.nodes.up[node_id];
+            let node_down_id = self.nodes.down[node_id];
+            self.nodes.up[node_down_id] = node_id;
+            self.nodes.down[node_up_id] = node_id;
+        }
     }
 
-    fn header_node_mut(&mut self, id: NodeId) -> &mut Node {
-        let header_node_id = self.node_mut(id).header;
+    /// Number of ways to branch on the column of the given node: one for each of its rows,
+    /// plus one for leaving the column when its lower bound has already been reached.
+    fn node_column_branches(&self, id: NodeId) -> usize {
+        let col = self.nodes.col(id);
+        let required = self.column_bounds[col].saturating_sub(self.column_slacks[col]);
 
-        self.node_mut(header_node_id)
+        (self.column_sizes[col] + 1).saturating_sub(required)
     }
 }
 
 #[derive(Debug, Copy, Clone)]
-struct Step {
-    node_id: NodeId,
-    backtracking: bool,
+enum Step {
+    /// Add the row of the node to the solution, or stop covering the column
+    /// if the node is a column header.
+    Forward(NodeId),
+    /// Remove the row of the node from the solution and try the next one.
+    Backward(NodeId),
+    /// Restore the column that was branched on. The node is the first row
+    /// of the column at the time of branching.
+    Restore(NodeId),
+    /// Report the rows selected before the search as a solution, as they cover
+    /// every primary column.
+    Selected,
+}
+
+/// Rows of a column in the order they are tried when the search is randomized
+#[derive(Debug, Copy, Clone)]
+struct ShuffledLevel {
+    /// Index of the first row of the level in `shuffled_rows`
+    start: usize,
+    /// Index of the row being tried, relative to `start`
+    position: usize,
+    /// Number of rows that have been removed from the column
+    tweaked: usize,
+    /// Generator as it was before the rows were shuffled
+    rng: Rng,
+}
+
+/// Whether a problem has no solutions, exactly one solution, or more
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Uniqueness {
+    None,
+    Unique(Vec<usize>),
+    /// The first two solutions that were found
+    Multiple(Vec<usize>, Vec<usize>),
 }
 
 #[derive(Debug, Default, Clone)]
-pub struct Solver {
+pub struct Solver<C = Mrv, O = NoObserver> {
     state: SolverState,
     step_stack: Vec<Step>,
     partial_solution: Vec<usize>,
+    chooser: C,
+    rng: Option<Rng>,
+    shuffled_rows: Vec<NodeId>,
+    shuffled_levels: Vec<ShuffledLevel>,
+    steps: u64,
+    /// Number of columns currently branched on
+    depth: usize,
+    /// Size of the step stack below which the search does not go,
+    /// so that the solver only explores a subtree of the search
+    floor: usize,
+    limits: Limits,
+    cancellation_token: Option<CancellationToken>,
+    interruption: Option<Interruption>,
+    initial: Initial,
+    observer: O,
+}
+
+/// Columns and rows that are selected before the search starts
+#[derive(Debug, Default, Clone)]
+struct Initial {
+    /// Ids of the initial columns in ascending order. They are covered once a row
+    /// contains them.
+    columns: Vec<usize>,
+    /// Indices of the initial columns that are currently covered, and their bounds
+    /// before they were covered
+    covered_columns: Vec<(usize, usize)>,
+    rows: Vec<usize>,
+    /// Rows that are selected after the initial rows until they are popped
+    assumptions: Vec<usize>,
+    /// First nodes of the initial rows and assumptions that are currently selected
+    selected_rows: Vec<NodeId>,
+    /// First nodes of the rows that are left out of the search
+    excluded_rows: Vec<NodeId>,
 }
 
 impl Solver {
     /// Creates a new solver for given rows. Columns in the rows are assumed to be in ascending order
     pub fn new(rows: Vec<Vec<usize>>, partial_solution: Vec<usize>) -> Self {
-        let column_count = rows.iter().flatten().copied().max().unwrap_or_default() + 1;
+        let mut builder = SolverBuilder::new();
+        builder.set_rows(rows);
+        builder.set_initial_columns(partial_solution);
+        builder.build()
+    }
+
+    /// Creates a new solver for given rows, checking that the columns of each row are
+    /// in ascending order without duplicates and that the initially covered columns
+    /// can be covered.
+    pub fn try_new(
+        rows: Vec<Vec<usize>>,
+        partial_solution: Vec<usize>,
+    ) -> Result<Self, SolverError> {
+        let mut builder = SolverBuilder::new();
+        builder.set_rows(rows);
+        builder.set_initial_columns(partial_solution);
+        builder.try_build()
+    }
+
+    /// Creates a new solver for exact cover with colors. Each row is a list of columns
+    /// with an optional color. Rows may share a secondary column as long as they assign
+    /// it the same color, while an uncolored secondary column is covered at most once.
+    pub fn with_colors(
+        rows: Vec<Vec<(usize, Option<usize>)>>,
+        secondary_columns: Vec<usize>,
+    ) -> Self {
+        let mut builder = SolverBuilder::new();
+        for row in rows {
+            builder.add_colored_row(row);
+        }
+        builder.set_secondary_columns(secondary_columns);
+        builder.build()
+    }
+}
+
+impl<C: ColumnChooser, O: Observer> Solver<C, O> {
+    fn from_builder(mut builder: SolverBuilder, chooser: C, observer: O) -> Self {
+        let mut initial_columns = builder.initial_columns.clone();
+        initial_columns.sort_unstable();
+        initial_columns.dedup();
+
+        let column_ids = builder.compact_columns();
+
+        // The indices are small enough not to overflow, as the ids were compacted otherwise
+        let column_count = builder.declared_columns().max().unwrap_or_default() + 1;
+
+        // Dense ids can leave gaps, which get no header
+        let mut declared_columns = vec![false; column_count];
+        for col_idx in builder.declared_columns() {
+            declared_columns[col_idx] = true;
+        }
+
+        let SolverBuilder {
+            rows,
+            columns: _,
+            secondary_columns,
+            column_bounds,
+            initial_columns: _,
+            initial_rows,
+            seed,
+        } = builder;
 
         let mut state = SolverState {
-            nodes: vec![],
+            nodes: Nodes::default(),
             header: Default::default(),
             column_sizes: vec![0; column_count],
+            column_bounds: vec![1; column_count],
+            column_limits: vec![1; column_count],
+            column_slacks: vec![0; column_count],
+            row_nodes: Vec::with_capacity(rows.len()),
+            column_headers: vec![NodeId::invalid(); column_count],
+            column_indices: column_ids
+                .iter()
+                .enumerate()
+                .map(|(col_idx, id)| (*id, col_idx))
+                .collect(),
+            column_ids,
+            stats: SearchStats::default(),
         };
 
-        let mut header_row: Vec<NodeId> = vec![];
+        for (col_idx, bounds) in column_bounds {
+            state.column_bounds[col_idx] = *bounds.end();
+            state.column_limits[col_idx] = *bounds.end();
+            state.column_slacks[col_idx] = bounds.end().saturating_sub(*bounds.start());
+        }
 
-        let mut above_nodes = vec![NodeId::invalid(); column_count];
+        for (col_idx, _) in rows.iter().flatten() {
+            state.column_sizes[*col_idx] += 1;
+        }
+
+        // The headers come before the nodes of the rows, in the order of their columns
+        let header_root_id = state.nodes.push(None, 0, NO_COLOR);
+        state.header = header_root_id;
+
+        let mut primary_columns = 0;
+        for (col_idx, declared) in declared_columns.into_iter().enumerate() {
+            if !declared {
+                continue;
+            }
+
+            let header_id = state.nodes.push(None, col_idx, NO_COLOR);
+            state.column_headers[col_idx] = header_id;
 
-        let mut columns_to_cover = BTreeMap::new();
+            // Secondary columns stay out of the header ring so that they are never
+            // chosen for branching, but they can still be covered by a row.
+            if !secondary_columns.contains(&col_idx) {
+                let last_header_id = state.nodes.left[header_root_id];
+                state.link_horizontal(last_header_id, header_id);
+                state.link_horizontal(header_id, header_root_id);
+                primary_columns += 1;
+            }
+        }
+
+        let node_count = rows.iter().map(Vec::len).sum::<usize>();
+        state.nodes.reserve(node_count);
 
         for (row_idx, row) in rows.into_iter().enumerate() {
             let mut first = NodeId::invalid();
             let mut prev = NodeId::invalid();
 
-            for col_idx in row {
-                let node_id = state.new_node();
-
-                state.node_mut(node_id).row = row_idx as isize;
-                state.node_mut(node_id).col = col_idx;
+            for (col_idx, color) in row {
+                let node_id = state.nodes.push(Some(row_idx), col_idx, color_code(color));
 
-                state.column_sizes[col_idx] += 1;
+                let header_id = state.column_headers[col_idx];
+                state.link_vertical(state.nodes.up[header_id], node_id);
+                state.link_vertical(node_id, header_id);
 
-                if !first.is_valid() {
+                if prev.is_valid() {
+                    state.link_horizontal(prev, node_id);
+                } else {
                     first = node_id;
                 }
+                prev = node_id;
+            }
 
-                if prev.is_valid() {
-                    state.link_horizontal(prev, node_id);
+            if first.is_valid() {
+                state.link_horizontal(prev, first);
+            }
+
+            state.row_nodes.push(first);
+        }
+
+        let mut solver = Self {
+            state,
+            partial_solution: Vec::with_capacity(primary_columns),
+            step_stack: vec![],
+            chooser,
+            rng: seed.map(Rng::new),
+            shuffled_rows: vec![],
+            shuffled_levels: vec![],
+            steps: 0,
+            depth: 0,
+            floor: 0,
+            limits: Limits::default(),
+            cancellation_token: None,
+            interruption: None,
+            initial: Initial {
+                columns: initial_columns,
+                covered_columns: vec![],
+                rows: initial_rows,
+                assumptions: vec![],
+                selected_rows: vec![],
+                excluded_rows: vec![],
+            },
+            observer,
+        };
+
+        solver.prepare();
+        solver
+    }
+
+    /// Covers the initial columns, initial rows and assumptions, and branches on the
+    /// first column. Nothing is searched if the rows can not be in the same solution.
+    fn prepare(&mut self) {
+        if !self.select_initial() {
+            return;
+        }
+
+        let header_root_id = self.state.header;
+        if self.state.nodes.right[header_root_id] != header_root_id {
+            self.branch();
+        } else if !self.initial.selected_rows.is_empty() {
+            self.step_stack.push(Step::Selected);
+        }
+    }
+
+    /// Leaves out the excluded rows and covers the initial columns, initial rows and
+    /// assumptions. Returns false without selecting any rows if they can not be
+    /// in the same solution.
+    fn select_initial(&mut self) -> bool {
+        let conflict = self.selection_conflict().is_some();
+
+        for i in 0..self.initial.excluded_rows.len() {
+            let first = self.initial.excluded_rows[i];
+
+            let mut current_id = first;
+            loop {
+                self.state.detach_node(current_id);
+
+                current_id = self.state.nodes.right[current_id];
+                if current_id == first {
+                    break;
                 }
+            }
+        }
 
-                let above_id = above_nodes[col_idx];
-                if above_id.is_valid() {
-                    let above_node = state.node_mut(above_id);
-                    let above_down_id = above_node.down;
-                    let above_header_id = above_node.header;
+        for i in 0..self.initial.columns.len() {
+            let Some(col_idx) = self.state.find_column(self.initial.columns[i]) else {
+                continue;
+            };
+            let header_id = self.state.column_headers[col_idx];
+            if !header_id.is_valid() {
+                continue;
+            }
 
-                    above_node.down = node_id;
+            self.cover(header_id);
 
-                    let node = state.node_mut(node_id);
-                    node.up = above_id;
-                    node.down = above_down_id;
-                    node.header = above_header_id;
+            let bound = std::mem::take(&mut self.state.column_bounds[col_idx]);
+            self.initial.covered_columns.push((col_idx, bound));
+        }
 
-                    state.header_node_mut(node_id).up = node_id;
-                } else {
-                    let header_id = state.new_node();
-                    header_row.push(header_id);
-
-                    let header = state.node_mut(header_id);
-                    header.row = -1;
-                    header.col = col_idx;
-                    header.header = header_id;
-                    header.up = node_id;
-                    header.down = node_id;
-
-                    let node = state.node_mut(node_id);
-                    node.up = header_id;
-                    node.down = header_id;
-                    node.header = header_id;
+        if conflict {
+            return false;
+        }
+
+        for row_idx in self.selection() {
+            let node_id = self.state.row_nodes[row_idx];
+            if node_id.is_valid() {
+                self.select_row(node_id);
+                self.initial.selected_rows.push(node_id);
+            }
+        }
+
+        true
+    }
+
+    /// Reverts everything done by [`prepare`](Self::prepare) and the search since then
+    fn unwind(&mut self) {
+        while let Some(step) = self.step_stack.pop() {
+            match step {
+                Step::Forward(_) | Step::Selected => {}
+                Step::Backward(node_id) => self.retreat(node_id),
+                Step::Restore(first_id) => self.restore(first_id),
+            }
+        }
+
+        while let Some(node_id) = self.initial.selected_rows.pop() {
+            self.deselect_row(node_id);
+        }
+
+        while let Some((col_idx, bound)) = self.initial.covered_columns.pop() {
+            self.state.column_bounds[col_idx] = bound;
+            self.uncover(self.state.column_headers[col_idx]);
+        }
+
+        for i in (0..self.initial.excluded_rows.len()).rev() {
+            let first = self.initial.excluded_rows[i];
+
+            let mut current_id = first;
+            loop {
+                current_id = self.state.nodes.left[current_id];
+                self.state.attach_node(current_id);
+
+                if current_id == first {
+                    break;
                 }
+            }
+        }
 
-                above_nodes[col_idx] = node_id;
-                prev = node_id;
+        self.floor = 0;
+        self.interruption = None;
+    }
 
-                if partial_solution.contains(&col_idx) && !columns_to_cover.contains_key(&col_idx) {
-                    columns_to_cover.insert(col_idx, node_id);
+    /// Describes the current problem as a builder, with the assumptions as initial rows
+    fn to_builder(&self) -> SolverBuilder {
+        // Nodes of a purified column have given up their color, which is the one
+        // that the row in the solution assigns to the column
+        let mut purified_colors = HashMap::new();
+        for row_idx in &self.partial_solution {
+            for node_id in self.state.row_node_ids(*row_idx) {
+                let color = self.state.nodes.color[node_id];
+                if color > NO_COLOR {
+                    purified_colors.insert(self.state.nodes.col(node_id), color);
                 }
             }
+        }
 
-            if first.is_valid() && prev.is_valid() {
-                state.link_horizontal(prev, first);
+        let mut builder = SolverBuilder::new();
+        let mut used_columns = HashSet::new();
+
+        for row_idx in 0..self.state.row_nodes.len() {
+            let mut row = self
+                .state
+                .row_node_ids(row_idx)
+                .into_iter()
+                .map(|node_id| {
+                    let col_idx = self.state.nodes.col(node_id);
+                    let color = match self.state.nodes.color[node_id] {
+                        PURIFIED => purified_colors[&col_idx],
+                        color => color,
+                    };
+
+                    let color = (color > NO_COLOR).then(|| color as usize - 1);
+                    (self.state.column_id(col_idx), color)
+                })
+                .collect::<Vec<_>>();
+
+            row.sort_unstable_by_key(|(column, _)| *column);
+            used_columns.extend(row.iter().map(|(column, _)| *column));
+            builder.add_colored_row(row);
+        }
+
+        for (col_idx, header_id) in self.state.column_headers.iter().copied().enumerate() {
+            if !header_id.is_valid() {
+                continue;
+            }
+
+            let column = self.state.column_id(col_idx);
+            if self.state.nodes.left[header_id] == header_id {
+                builder.secondary_columns.push(column);
+            } else if !used_columns.contains(&column) {
+                builder.add_column(column);
+            }
+
+            let max = self.state.column_limits[col_idx];
+            let min = max - self.state.column_slacks[col_idx];
+            if (min, max) != (1, 1) {
+                builder.set_column_bounds(column, min..=max);
             }
         }
 
-        header_row.sort_by(|a, b| {
-            let a_col = state.node_mut(*a).col;
-            let b_col = state.node_mut(*b).col;
-            a_col.cmp(&b_col)
-        });
+        builder.secondary_columns.sort_unstable();
+        builder.set_initial_columns(self.initial.columns.clone());
+        builder.set_initial_rows(self.selection());
+        builder
+    }
 
-        let Some(first_header_id) = header_row.first().copied() else {
-            return Default::default();
-        };
+    /// Returns the initial rows followed by the assumptions
+    fn selection(&self) -> Vec<usize> {
+        let mut rows = self.initial.rows.clone();
+        rows.extend_from_slice(&self.initial.assumptions);
+        rows
+    }
 
-        let last_header_id = header_row.iter().last().copied().unwrap_or(first_header_id);
+    /// Finds the first initial row or assumption that can not be in the same solution
+    /// with the initial columns and the rows before it. The columns must not be covered yet.
+    fn selection_conflict(&self) -> Option<SolverError> {
+        // Last row to use each column, how many times it has been used, and with which color
+        let mut used_columns: BTreeMap<usize, (usize, usize, i32)> = BTreeMap::new();
 
-        state.node_mut(first_header_id).left = last_header_id;
-        state.node_mut(last_header_id).right = first_header_id;
+        let rows = self.selection();
 
-        header_row.windows(2).for_each(|nodes| {
-            state.link_horizontal(nodes[0], nodes[1]);
-        });
+        for (i, row_idx) in rows.iter().copied().enumerate() {
+            let Some(first) = self.state.row_nodes.get(row_idx).copied() else {
+                return Some(SolverError::InvalidRow { row: row_idx });
+            };
 
-        let header_root_id = state.new_node();
+            if rows[..i].contains(&row_idx) {
+                return Some(SolverError::ConflictingRows {
+                    first: row_idx,
+                    second: row_idx,
+                });
+            }
 
-        state.node_mut(header_root_id).right = first_header_id;
-        state.node_mut(first_header_id).left = header_root_id;
+            if !first.is_valid() {
+                continue;
+            }
 
-        state.node_mut(header_root_id).left = last_header_id;
-        state.node_mut(last_header_id).right = header_root_id;
+            let mut node_id = first;
+            loop {
+                let col = self.state.nodes.col(node_id);
+                let node_color = self.state.nodes.color[node_id];
+                let column = self.state.column_id(col);
 
-        state.header = header_root_id;
+                if self.initial.columns.contains(&column) {
+                    return Some(SolverError::ConflictingCoveredColumn { column });
+                }
 
-        let mut solver = Self {
-            state: state.clone(),
-            partial_solution: Vec::with_capacity(header_row.len()),
-            step_stack: vec![],
-        };
+                match used_columns.get_mut(&col) {
+                    None => {
+                        used_columns.insert(col, (row_idx, 1, node_color));
+                    }
+                    Some((last_row_idx, count, color))
+                        if (node_color != NO_COLOR && node_color == *color)
+                            || (node_color == NO_COLOR
+                                && *color == NO_COLOR
+                                && *count < self.state.column_bounds[col]) =>
+                    {
+                        *last_row_idx = row_idx;
+                        *count += 1;
+                    }
+                    Some((last_row_idx, _, _)) => {
+                        return Some(SolverError::ConflictingRows {
+                            first: *last_row_idx,
+                            second: row_idx,
+                        });
+                    }
+                }
 
-        for column_node_id in columns_to_cover.values() {
-            let column_first_node_id = state.header_node_mut(*column_node_id).down;
-            solver.cover(column_first_node_id);
+                node_id = self.state.nodes.right[node_id];
+                if node_id == first {
+                    break;
+                }
+            }
         }
 
-        if let Some(node_id) = solver.choose_column() {
-            solver.step_stack.push(Step {
-                node_id,
-                backtracking: false,
-            });
+        None
+    }
+
+    /// Discards the search so far and starts it again from the beginning
+    pub fn restart(&mut self) {
+        self.unwind();
+        self.prepare();
+    }
+
+    /// Adds the row of the node to the solution outside of the search.
+    fn select_row(&mut self, node_id: NodeId) {
+        let mut current_id = node_id;
+        loop {
+            self.state.detach_node(current_id);
+
+            current_id = self.state.nodes.right[current_id];
+            if current_id == node_id {
+                break;
+            }
         }
 
-        solver
+        loop {
+            self.commit(current_id);
+
+            current_id = self.state.nodes.right[current_id];
+            if current_id == node_id {
+                break;
+            }
+        }
+
+        let row = self.state.nodes.row(node_id).unwrap();
+        self.partial_solution.push(row);
     }
 
-    fn choose_column(&self) -> Option<NodeId> {
-        let mut best_column_id = None;
-        let mut best_size = usize::MAX;
+    /// Removes the row of the node from the solution, reverting [`select_row`](Self::select_row).
+    fn deselect_row(&mut self, node_id: NodeId) {
+        self.partial_solution.pop();
+
+        let mut current_id = node_id;
+        loop {
+            current_id = self.state.nodes.left[current_id];
+            self.uncommit(current_id);
 
-        let mut current_node_id = self.state.node(self.state.header).right;
+            if current_id == node_id {
+                break;
+            }
+        }
 
-        while current_node_id != self.state.header {
-            let current_size = self.state.node_column_size(current_node_id);
+        loop {
+            current_id = self.state.nodes.left[current_id];
+            self.state.attach_node(current_id);
 
-            if current_size < best_size {
-                best_column_id = Some(current_node_id);
-                best_size = current_size;
+            if current_id == node_id {
+                break;
             }
-            current_node_id = self.state.node(current_node_id).right;
         }
+    }
+
+    fn choose_column(&mut self) -> Option<NodeId> {
+        let columns = Columns::new(&self.state);
+        let first_column = columns.clone().next()?;
+
+        let column = self.chooser.choose(columns).unwrap_or(first_column);
+
+        Some(column.node_id)
+    }
 
-        Some(self.state.node(best_column_id?).down)
+    /// Replaces the strategy for choosing columns in the rest of the search
+    pub fn with_chooser<D: ColumnChooser>(self, chooser: D) -> Solver<D, O> {
+        Solver {
+            state: self.state,
+            step_stack: self.step_stack,
+            partial_solution: self.partial_solution,
+            chooser,
+            rng: self.rng,
+            shuffled_rows: self.shuffled_rows,
+            shuffled_levels: self.shuffled_levels,
+            steps: self.steps,
+            depth: self.depth,
+            floor: self.floor,
+            limits: self.limits,
+            cancellation_token: self.cancellation_token,
+            interruption: self.interruption,
+            initial: self.initial,
+            observer: self.observer,
+        }
+    }
+
+    /// Sends the events of the search from now on to the observer. The initial rows and
+    /// the first column are chosen when the solver is built, so to observe the whole
+    /// search, build it with [`SolverBuilder::build_with_observer`] instead.
+    pub fn with_observer<P: Observer>(self, observer: P) -> Solver<C, P> {
+        Solver {
+            state: self.state,
+            step_stack: self.step_stack,
+            partial_solution: self.partial_solution,
+            chooser: self.chooser,
+            rng: self.rng,
+            shuffled_rows: self.shuffled_rows,
+            shuffled_levels: self.shuffled_levels,
+            steps: self.steps,
+            depth: self.depth,
+            floor: self.floor,
+            limits: self.limits,
+            cancellation_token: self.cancellation_token,
+            interruption: self.interruption,
+            initial: self.initial,
+            observer,
+        }
+    }
+
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
     }
 
     pub fn partial_solution(&self) -> &[usize] {
@@ -292,123 +809,511 @@ impl Solver {
     }
 
     pub fn is_completed(&self) -> bool {
-        self.step_stack.is_empty()
+        self.step_stack.len() <= self.floor
+    }
+
+    /// Limits the work done by the search from now on. Once the budget is exhausted,
+    /// the search stops until it is given a new budget.
+    pub fn set_budget(&mut self, budget: SearchBudget) {
+        self.limits = Limits::new(budget, self.steps, self.state.stats.updates);
+        self.interruption = None;
+    }
+
+    /// Sets a token that can be used to cancel the search from another thread.
+    /// A cancelled search can be resumed with a new token.
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation_token = Some(token);
+        self.interruption = None;
+    }
+
+    /// Returns the counts of the work done by the search so far
+    pub fn stats(&self) -> &SearchStats {
+        &self.state.stats
+    }
+
+    /// Resets the counts of the work done by the search. Does not affect the budget.
+    pub fn reset_stats(&mut self) {
+        self.limits.rebase_updates(self.state.stats.updates);
+        self.state.stats = SearchStats::default();
+    }
+
+    /// Returns the reason why the search was last interrupted,
+    /// if it was interrupted and has not been resumed since
+    pub fn interruption(&self) -> Option<Interruption> {
+        self.interruption
     }
 
     fn cover(&mut self, node_id: NodeId) {
+        let col = self.state.nodes.col(node_id);
+        self.observer.cover(self.state.column_id(col));
         self.state.detach_column(node_id);
 
-        let node = self.state.node_mut(node_id);
-        let node_header_id = node.header;
+        let node_header_id = self.state.column_headers[col];
 
-        let mut down_id = self.state.node_mut(node_header_id).down;
+        let mut down_id = self.state.nodes.down[node_header_id];
         while down_id != node_header_id {
             self.state.detach_row(down_id);
 
-            down_id = self.state.node_mut(down_id).down;
+            down_id = self.state.nodes.down[down_id];
         }
     }
 
     fn uncover(&mut self, node_id: NodeId) {
-        let node_header_id = self.state.node(node_id).header;
-        let mut up_id = self.state.node(node_header_id).up;
+        let node_header_id = self.state.header_of(node_id);
+        let mut up_id = self.state.nodes.up[node_header_id];
 
         while up_id != node_header_id {
             self.state.attach_row(up_id);
-            up_id = self.state.node(up_id).up;
+            up_id = self.state.nodes.up[up_id];
         }
 
         self.state.attach_column(node_id);
+
+        let col = self.state.nodes.col(node_id);
+        self.observer.uncover(self.state.column_id(col));
+    }
+
+    /// Hides the rows that assign a different color to the column of the node
+    /// and marks the rows that assign the same color as purified.
+    fn purify(&mut self, node_id: NodeId) {
+        let node_header_id = self.state.header_of(node_id);
+        let node_color = self.state.nodes.color[node_id];
+
+        let col = self.state.nodes.col(node_id);
+        self.observer
+            .purify(self.state.column_id(col), node_color as usize - 1);
+
+        let mut down_id = self.state.nodes.down[node_header_id];
+        while down_id != node_header_id {
+            if self.state.nodes.color[down_id] != node_color {
+                self.state.detach_row(down_id);
+            } else if down_id != node_id {
+                self.state.nodes.color[down_id] = PURIFIED;
+            }
+
+            down_id = self.state.nodes.down[down_id];
+        }
+    }
+
+    fn unpurify(&mut self, node_id: NodeId) {
+        let node_header_id = self.state.header_of(node_id);
+        let node_color = self.state.nodes.color[node_id];
+
+        let mut up_id = self.state.nodes.up[node_header_id];
+        while up_id != node_header_id {
+            if self.state.nodes.color[up_id] == PURIFIED {
+                self.state.nodes.color[up_id] = node_color;
+            } else if up_id != node_id {
+                self.state.attach_row(up_id);
+            }
+
+            up_id = self.state.nodes.up[up_id];
+        }
+
+        let col = self.state.nodes.col(node_id);
+        self.observer
+            .unpurify(self.state.column_id(col), node_color as usize - 1);
+    }
+
+    /// Uses the column of the node once more, covering it when its upper bound is reached.
+    /// A colored column is purified instead, unless it already is.
+    fn commit(&mut self, node_id: NodeId) {
+        let col = self.state.nodes.col(node_id);
+
+        match self.state.nodes.color[node_id] {
+            NO_COLOR => {
+                self.state.column_bounds[col] -= 1;
+                if self.state.column_bounds[col] == 0 {
+                    self.cover(node_id);
+                }
+            }
+            PURIFIED => {}
+            _ => self.purify(node_id),
+        }
+    }
+
+    fn uncommit(&mut self, node_id: NodeId) {
+        let col = self.state.nodes.col(node_id);
+
+        match self.state.nodes.color[node_id] {
+            NO_COLOR => {
+                if self.state.column_bounds[col] == 0 {
+                    self.uncover(node_id);
+                }
+                self.state.column_bounds[col] += 1;
+            }
+            PURIFIED => {}
+            _ => self.unpurify(node_id),
+        }
+    }
+
+    /// Removes the row of the node from the solver and drops the node from its column,
+    /// which must be the first node in the column.
+    fn tweak(&mut self, node_id: NodeId, hide: bool) {
+        if hide {
+            self.state.detach_row(node_id);
+        }
+
+        let node_col = self.state.nodes.col(node_id);
+        let node_header_id = self.state.column_headers[node_col];
+        let node_down_id = self.state.nodes.down[node_id];
+
+        self.state.nodes.down[node_header_id] = node_down_id;
+        self.state.nodes.up[node_down_id] = node_header_id;
+
+        self.state.column_sizes[node_col] -= 1;
+        self.state.stats.updates += 1;
+    }
+
+    /// Reverts all tweaks made to a column since the given node was its first node.
+    fn untweak(&mut self, first_id: NodeId, unhide: bool) {
+        let col = self.state.nodes.col(first_id);
+        let header_id = self.state.column_headers[col];
+
+        let last_id = self.state.nodes.down[header_id];
+        self.state.nodes.down[header_id] = first_id;
+
+        let mut above_id = header_id;
+        let mut current_id = first_id;
+        while current_id != last_id {
+            self.state.nodes.up[current_id] = above_id;
+            self.state.column_sizes[col] += 1;
+
+            if unhide {
+                self.state.attach_row(current_id);
+            }
+
+            above_id = current_id;
+            current_id = self.state.nodes.down[current_id];
+        }
+
+        self.state.nodes.up[last_id] = above_id;
+    }
+
+    /// Chooses a column and pushes the steps for branching on it.
+    /// Returns `true` if there are no columns left, meaning that a solution was found.
+    fn branch(&mut self) -> bool {
+        let Some(column_id) = self.choose_column() else {
+            self.state.stats.add_solution(self.depth);
+            return true;
+        };
+
+        let col = self.state.nodes.col(column_id);
+        let size = self.state.column_sizes[col];
+        self.observer.choose_column(self.state.column_id(col), size);
+
+        self.branch_on(column_id);
+        false
+    }
+
+    /// Pushes the steps for branching on the column of the given header
+    fn branch_on(&mut self, column_id: NodeId) {
+        let col = self.state.nodes.col(column_id);
+        self.state.column_bounds[col] -= 1;
+        if self.state.column_bounds[col] == 0 {
+            self.cover(column_id);
+        }
+
+        let first_id = match &mut self.rng {
+            Some(rng) => {
+                let start = self.shuffled_rows.len();
+                let unshuffled = *rng;
+
+                let mut current_id = self.state.nodes.down[column_id];
+                while current_id != column_id {
+                    self.shuffled_rows.push(current_id);
+                    current_id = self.state.nodes.down[current_id];
+                }
+
+                let rows = &mut self.shuffled_rows[start..];
+                for i in (1..rows.len()).rev() {
+                    rows.swap(i, rng.below(i + 1));
+                }
+
+                self.shuffled_levels.push(ShuffledLevel {
+                    start,
+                    position: 0,
+                    tweaked: 0,
+                    rng: unshuffled,
+                });
+
+                rows.first().copied().unwrap_or(column_id)
+            }
+            None => self.state.nodes.down[column_id],
+        };
+
+        self.step_stack.push(Step::Restore(first_id));
+        self.step_stack.push(Step::Forward(first_id));
+        self.depth += 1;
+    }
+
+    /// Reverts the removal of the rows that were tried on the current shuffled level.
+    fn unshuffle(&mut self, unhide: bool) {
+        let Some(level) = self.shuffled_levels.pop() else {
+            return;
+        };
+
+        for i in (level.start..level.start + level.tweaked).rev() {
+            let node_id = self.shuffled_rows[i];
+
+            self.state.attach_node(node_id);
+            if unhide {
+                self.state.attach_row(node_id);
+            }
+        }
+
+        self.shuffled_rows.truncate(level.start);
+    }
+
+    fn restore(&mut self, first_id: NodeId) {
+        self.depth -= 1;
+
+        let col = self.state.nodes.col(first_id);
+        let column_id = self.state.column_headers[col];
+
+        let bound = self.state.column_bounds[col];
+        let slack = self.state.column_slacks[col];
+
+        if self.rng.is_some() {
+            self.unshuffle(bound != 0);
+            if bound == 0 {
+                self.uncover(column_id);
+            }
+        } else if bound == 0 && slack == 0 {
+            self.uncover(column_id);
+        } else if bound == 0 {
+            self.untweak(first_id, false);
+            self.uncover(column_id);
+        } else {
+            self.untweak(first_id, true);
+        }
+
+        self.state.column_bounds[col] += 1;
     }
 
     pub fn step(&mut self) -> Option<Vec<usize>> {
-        let Step {
-            node_id,
-            backtracking,
-        } = self.step_stack.pop()?;
+        self.advance()?.then(|| self.partial_solution.clone())
+    }
 
-        let node_header_id = self.state.node(node_id).header;
+    /// Takes a single step in the search. Returns `Some(true)` if a solution was found,
+    /// in which case it is the current partial solution, and `None` if the search
+    /// is completed or interrupted.
+    fn advance(&mut self) -> Option<bool> {
+        if self.is_completed() {
+            return None;
+        }
 
-        if node_id == node_header_id {
+        self.interruption = self
+            .limits
+            .check(self.steps, self.state.stats.updates)
+            .or_else(|| {
+                let token = self.cancellation_token.as_ref()?;
+                token.is_cancelled().then_some(Interruption::Cancelled)
+            });
+
+        if self.interruption.is_some() {
             return None;
         }
 
-        if backtracking {
-            self.step_backward(node_id);
-        } else {
-            self.step_forward(node_id);
+        self.steps += 1;
+
+        let solution_found = match self.step_stack.pop()? {
+            Step::Forward(node_id) => self.step_forward(node_id),
+            Step::Backward(node_id) => {
+                self.step_backward(node_id);
+                false
+            }
+            Step::Restore(node_id) => {
+                self.restore(node_id);
+                false
+            }
+            Step::Selected => {
+                self.state.stats.add_solution(self.depth);
+                true
+            }
+        };
+
+        if solution_found {
+            self.observer.solution(&self.partial_solution);
         }
 
-        let header_root_id = self.state.header;
+        Some(solution_found)
+    }
 
-        if self.state.node_mut(header_root_id).right == header_root_id {
-            Some(self.partial_solution.clone())
-        } else {
-            None
+    /// Finds the next solution. Unlike [`Iterator::next`], this tells apart
+    /// a completed search from an interrupted one.
+    pub fn try_next(&mut self) -> Result<Option<Vec<usize>>, Interruption> {
+        match self.next() {
+            Some(solution) => Ok(Some(solution)),
+            None => self.interruption.map_or(Ok(None), Err),
         }
     }
 
-    fn step_forward(&mut self, node_id: NodeId) {
-        let node_row = self.state.node(node_id).row;
-        self.partial_solution.push(node_row as _);
+    /// Checks whether there is exactly one remaining solution.
+    /// The search stops as soon as a second solution is found, or when it is interrupted.
+    pub fn uniqueness(&mut self) -> Result<Uniqueness, Interruption> {
+        let Some(first) = self.try_next()? else {
+            return Ok(Uniqueness::None);
+        };
 
-        let mut current_id = node_id;
-        loop {
-            self.cover(current_id);
+        match self.try_next()? {
+            Some(second) => Ok(Uniqueness::Multiple(first, second)),
+            None => Ok(Uniqueness::Unique(first)),
+        }
+    }
 
-            current_id = self.state.node(current_id).right;
-            if current_id == node_id {
-                break;
+    /// Counts the remaining solutions without collecting them.
+    /// Like [`Iterator::count`], this consumes the solutions, including the ones counted
+    /// before the search is interrupted. To keep count across interruptions, use
+    /// [`count_solutions_up_to`](Self::count_solutions_up_to) instead.
+    pub fn count_solutions(&mut self) -> Result<u128, Interruption> {
+        let count = self.count_solutions_up_to(u128::MAX);
+        self.interruption.map_or(Ok(count), Err)
+    }
+
+    /// Counts the remaining solutions, stopping once `limit` solutions have been found
+    /// or the search is interrupted. The search can be continued afterwards.
+    pub fn count_solutions_up_to(&mut self, limit: u128) -> u128 {
+        let mut count = 0;
+
+        while count < limit {
+            match self.advance() {
+                Some(true) => count += 1,
+                Some(false) => {}
+                None => break,
             }
         }
 
-        self.step_stack.push(Step {
-            node_id,
-            backtracking: true,
-        });
+        count
+    }
 
-        if let Some(node_id) = self.choose_column() {
-            self.step_stack.push(Step {
-                node_id,
-                backtracking: false,
-            });
+    fn step_forward(&mut self, node_id: NodeId) -> bool {
+        self.enter(node_id) && self.branch()
+    }
+
+    /// Adds the row of the node to the solution, or leaves the column uncovered if the node
+    /// is its header, and pushes the step for backtracking. Returns `false` if the node can
+    /// not lead to a solution.
+    fn enter(&mut self, node_id: NodeId) -> bool {
+        let col = self.state.nodes.col(node_id);
+        let column_id = self.state.column_headers[col];
+
+        let bound = self.state.column_bounds[col];
+        let slack = self.state.column_slacks[col];
+
+        if bound == 0 && slack == 0 {
+            if node_id == column_id {
+                return false;
+            }
+        } else {
+            if self.state.column_sizes[col] + slack <= bound {
+                // Not enough rows left to reach the lower bound of the column
+                return false;
+            }
+
+            if node_id != column_id {
+                if let Some(level) = self.shuffled_levels.last_mut() {
+                    // Shuffled rows are not removed in column order, so they are
+                    // detached one by one and attached back in reverse order.
+                    level.tweaked += 1;
+                    if bound != 0 {
+                        self.state.detach_row(node_id);
+                    }
+                    self.state.detach_node(node_id);
+                } else {
+                    self.tweak(node_id, bound != 0);
+                }
+            } else if bound != 0 {
+                self.state.detach_column(column_id);
+            }
+        }
+
+        if node_id != column_id {
+            let row = self.state.nodes.row(node_id).unwrap();
+            self.partial_solution.push(row);
+
+            let mut current_id = self.state.nodes.right[node_id];
+            while current_id != node_id {
+                self.commit(current_id);
+                current_id = self.state.nodes.right[current_id];
+            }
         }
+
+        self.step_stack.push(Step::Backward(node_id));
+        self.state.stats.add_node(self.depth - 1);
+
+        let row = self.state.nodes.row(node_id);
+        self.observer.step_forward(self.state.column_id(col), row);
+
+        true
     }
 
     fn step_backward(&mut self, node_id: NodeId) {
-        self.partial_solution.pop();
+        self.state.stats.backtracks += 1;
+        self.retreat(node_id);
 
-        let mut current_id = self.state.node(node_id).left;
-        loop {
-            self.uncover(current_id);
+        let col = self.state.nodes.col(node_id);
+        let column_id = self.state.column_headers[col];
 
-            if current_id == node_id {
-                break;
+        let row = self.state.nodes.row(node_id);
+        self.observer.step_backward(self.state.column_id(col), row);
+
+        if node_id == column_id {
+            return;
+        }
+
+        let node_down = match self.shuffled_levels.last_mut() {
+            Some(level) => {
+                level.position += 1;
+
+                let end = self.shuffled_rows.len();
+                let next = level.start + level.position;
+                if next < end {
+                    self.shuffled_rows[next]
+                } else {
+                    column_id
+                }
+            }
+            None => self.state.nodes.down[node_id],
+        };
+
+        let exactly_once = self.state.column_bounds[col] == 0 && self.state.column_slacks[col] == 0;
+        if node_down != column_id || !exactly_once {
+            self.step_stack.push(Step::Forward(node_down));
+        }
+    }
+
+    /// Reverts the step forward that tried the node
+    fn retreat(&mut self, node_id: NodeId) {
+        let col = self.state.nodes.col(node_id);
+        let column_id = self.state.column_headers[col];
+
+        if node_id == column_id {
+            if self.state.column_bounds[col] != 0 {
+                self.state.attach_column(column_id);
             }
-            current_id = self.state.node(current_id).left;
+            return;
         }
 
-        let node_down = self.state.node(node_id).down;
-        let node_header = self.state.node(node_id).header;
+        self.partial_solution.pop();
 
-        if node_down != node_header {
-            self.step_stack.push(Step {
-                node_id: node_down,
-                backtracking: false,
-            });
+        let mut current_id = self.state.nodes.left[node_id];
+        while current_id != node_id {
+            self.uncommit(current_id);
+            current_id = self.state.nodes.left[current_id];
         }
     }
 }
 
-impl Iterator for Solver {
+impl<C: ColumnChooser, O: Observer> Iterator for Solver<C, O> {
     type Item = Vec<usize>;
 
+    /// Finds the next solution. Returns `None` when the search is completed or interrupted,
+    /// which can be told apart with [`Solver::interruption`].
     fn next(&mut self) -> Option<Self::Item> {
-        while !self.is_completed() {
-            let step = self.step();
-
-            if step.is_some() {
-                return step;
+        while let Some(solution_found) = self.advance() {
+            if solution_found {
+                return Some(self.partial_solution.clone());
             }
         }
 
@@ -447,4 +1352,412 @@ mod tests {
 
         assert_eq!(vec![vec![2]], solutions);
     }
+
+    pub(crate) fn n_queens(n: usize) -> SolverBuilder {
+        let mut builder = SolverBuilder::new();
+
+        for row in 0..n {
+            for col in 0..n {
+                builder.add_row(vec![row, n + col, 2 * n + row + col, 5 * n + row - col]);
+            }
+        }
+
+        builder.set_secondary_columns((2 * n..7 * n).collect());
+        builder
+    }
+
+    /// Returns a random row with one of the first `primary_count` columns, which are primary,
+    /// and some of the secondary columns below `column_count`, with or without a color
+    pub(crate) fn random_colored_row(rng: &mut Rng, primary_count: usize, column_count: usize) -> Vec<(usize, Option<usize>)> {
+        let mut row = vec![(rng.below(primary_count), None)];
+        for col in primary_count..column_count {
+            match rng.below(3) {
+                0 => row.push((col, None)),
+                1 => row.push((col, Some(rng.below(2)))),
+                _ => {}
+            }
+        }
+        row
+    }
+
+    /// Sorts each solution and then the solutions, to compare them regardless of their order
+    pub(crate) fn sorted<T: Ord>(solutions: impl IntoIterator<Item = Vec<T>>) -> Vec<Vec<T>> {
+        let mut solutions = solutions.into_iter().map(|mut solution| { solution.sort(); solution }).collect::<Vec<_>>();
+        solutions.sort();
+        solutions
+    }
+
+    #[test]
+    fn test_secondary_columns() {
+        assert_eq!(2, n_queens(4).build().count());
+        assert_eq!(92, n_queens(8).build().count());
+
+        let mut builder = SolverBuilder::new();
+        builder.set_rows(vec![
+            vec![0, 2],
+            vec![1, 2],
+            vec![0],
+            vec![1],
+        ]);
+        builder.set_secondary_columns(vec![2]);
+
+        let solutions = builder.build().collect::<Vec<_>>();
+
+        assert_eq!(vec![vec![0, 3], vec![2, 1], vec![2, 3]], solutions);
+    }
+
+    #[test]
+    fn test_column_bounds() {
+        // Column 0 must be covered 2 to 3 times and column 1 exactly twice
+        let mut builder = SolverBuilder::new();
+        builder.set_rows(vec![
+            vec![0],
+            vec![0, 1],
+            vec![0, 1],
+            vec![1],
+        ]);
+        builder.set_column_bounds(0, 2..=3);
+        builder.set_column_bounds(1, 2..=2);
+
+        let solutions = sorted(builder.build());
+
+        assert_eq!(vec![
+            vec![0, 1, 2],
+            vec![0, 1, 3],
+            vec![0, 2, 3],
+            vec![1, 2],
+        ], solutions);
+    }
+
+    #[test]
+    fn test_column_bounds_against_brute_force() {
+        let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move |n: usize| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed % n as u64) as usize
+        };
+
+        for iteration in 0..200 {
+            let column_count = 1 + next(5);
+            let rows = (0..1 + next(9)).map(|_| {
+                let row = (0..column_count).filter(|_| next(3) == 0).collect::<Vec<_>>();
+                if row.is_empty() { vec![next(column_count)] } else { row }
+            }).collect::<Vec<_>>();
+            let bounds = (0..column_count).map(|_| {
+                let min = next(3);
+                min..=(min + next(3)).max(1)
+            }).collect::<Vec<_>>();
+            let secondary = (0..column_count).filter(|_| next(4) == 0).collect::<Vec<_>>();
+
+            let mut expected = vec![];
+            for subset in 0..1usize << rows.len() {
+                let mut counts = vec![0; column_count];
+                let mut chosen = vec![];
+                for (row_idx, row) in rows.iter().enumerate() {
+                    if subset & (1 << row_idx) != 0 {
+                        chosen.push(row_idx);
+                        for col in row { counts[*col] += 1; }
+                    }
+                }
+                // Every column has bounds, so the columns that no row contains also count
+                let valid = (0..column_count).all(|col| {
+                    let min = if secondary.contains(&col) { 0 } else { *bounds[col].start() };
+                    (min..=*bounds[col].end()).contains(&counts[col])
+                });
+                // Rows without primary columns are never chosen
+                let primary = chosen.iter().all(|row_idx| rows[*row_idx].iter().any(|col| !secondary.contains(col)));
+                if valid && primary && !chosen.is_empty() {
+                    expected.push(chosen);
+                }
+            }
+            expected.sort();
+
+            let mut builder = SolverBuilder::new();
+            builder.set_rows(rows.clone());
+            builder.set_secondary_columns(secondary.clone());
+            for (col, bounds) in bounds.iter().enumerate() {
+                builder.set_column_bounds(col, bounds.clone());
+            }
+            if iteration % 2 == 1 {
+                builder.set_seed(iteration);
+            }
+
+            let solutions = sorted(builder.build().filter(|solution| !solution.is_empty()));
+
+            if rows.iter().flatten().any(|col| !secondary.contains(col)) {
+                assert_eq!(expected, solutions, "rows: {:?}, bounds: {:?}, secondary: {:?}", rows, bounds, secondary);
+            }
+        }
+    }
+
+    #[test]
+    fn test_colors() {
+        // Two words must be placed in columns 0 and 1, and they must agree on
+        // the letter in secondary column 2
+        let solver = Solver::with_colors(vec![
+            vec![(0, None), (2, Some(0))],
+            vec![(0, None), (2, Some(1))],
+            vec![(1, None), (2, Some(1))],
+            vec![(1, None), (2, None)],
+            vec![(1, None), (2, Some(0))],
+        ], vec![2]);
+
+        let solutions = solver.collect::<Vec<_>>();
+
+        assert_eq!(vec![vec![0, 4], vec![1, 2]], solutions);
+    }
+
+    #[test]
+    fn test_colors_against_brute_force() {
+        let mut rng = Rng::new(0x9e37_79b9_7f4a_7c15);
+
+        for _ in 0..200 {
+            let primary_count = 1 + rng.below(3);
+            let column_count = primary_count + 1 + rng.below(3);
+            let rows = (0..1 + rng.below(9))
+                .map(|_| random_colored_row(&mut rng, primary_count, column_count))
+                .collect::<Vec<_>>();
+
+            let mut expected = vec![];
+            for subset in 1..1usize << rows.len() {
+                let chosen = (0..rows.len()).filter(|row_idx| subset & (1 << row_idx) != 0).collect::<Vec<_>>();
+                let colors = |col| chosen.iter().flat_map(|row_idx| rows[*row_idx].iter().filter(move |(c, _)| *c == col)).map(|(_, color)| *color).collect::<Vec<_>>();
+
+                let primary_valid = (0..primary_count).all(|col| {
+                    let used = rows.iter().flatten().any(|(c, _)| *c == col);
+                    !used || colors(col).len() == 1
+                });
+                let secondary_valid = (primary_count..column_count).all(|col| {
+                    let colors = colors(col);
+                    colors.len() <= 1 || colors.iter().all(|color| color.is_some() && *color == colors[0])
+                });
+                if primary_valid && secondary_valid {
+                    expected.push(chosen);
+                }
+            }
+            expected.sort();
+
+            let solver = Solver::with_colors(rows.clone(), (primary_count..column_count).collect());
+
+            assert_eq!(expected, sorted(solver), "rows: {:?}", rows);
+        }
+    }
+
+    #[test]
+    fn test_try_new() {
+        assert!(Solver::try_new(vec![vec![0, 1], vec![1, 2]], vec![0]).is_ok());
+
+        assert_eq!(Some(SolverError::EmptyRow { row: 1 }), Solver::try_new(vec![vec![0], vec![]], vec![]).err());
+        assert_eq!(Some(SolverError::UnsortedColumns { row: 0 }), Solver::try_new(vec![vec![1, 0]], vec![]).err());
+        assert_eq!(Some(SolverError::DuplicateColumn { row: 1, column: 2 }), Solver::try_new(vec![vec![0], vec![1, 2, 2]], vec![]).err());
+        assert_eq!(Some(SolverError::UncoverableColumn { column: 3 }), Solver::try_new(vec![vec![0, 1]], vec![3]).err());
+        assert_eq!(Some(SolverError::ConflictingCoveredColumn { column: 1 }), Solver::try_new(vec![vec![0, 1]], vec![1, 0, 1]).err());
+
+        let mut builder = SolverBuilder::new();
+        builder.add_colored_row(vec![(0, Some(1)), (1, None)]);
+        assert_eq!(Some(SolverError::ColoredPrimaryColumn { row: 0, column: 0 }), builder.try_build().err());
+
+        let mut builder = SolverBuilder::new();
+        builder.add_colored_row(vec![(0, None), (1, Some(usize::MAX - 1))]);
+        builder.set_secondary_columns(vec![1]);
+        assert_eq!(Some(SolverError::ColorTooLarge { row: 0, column: 1 }), builder.try_build().err());
+
+        let mut builder = SolverBuilder::new();
+        builder.add_colored_row(vec![(0, None), (1, Some(1 << 31))]);
+        builder.set_secondary_columns(vec![1]);
+        assert!(std::panic::catch_unwind(|| builder.build()).is_err());
+
+        let mut builder = SolverBuilder::new();
+        builder.add_row(vec![0, 1]);
+        builder.set_column_bounds(1, 0..=0);
+        assert_eq!(Some(SolverError::InvalidColumnBounds { column: 1 }), builder.try_build().err());
+    }
+
+    #[test]
+    fn test_initial_rows() {
+        let rows = vec![
+            vec![0, 1],
+            vec![0, 2],
+            vec![1, 3],
+            vec![2, 3],
+            vec![0, 1, 2],
+            vec![1, 2, 3],
+        ];
+
+        let mut builder = SolverBuilder::new();
+        builder.set_rows(rows.clone());
+        builder.set_initial_rows(vec![2]);
+
+        assert_eq!(vec![vec![2, 1]], builder.try_build().unwrap().collect::<Vec<_>>());
+
+        let mut builder = SolverBuilder::new();
+        builder.set_rows(rows.clone());
+        builder.set_initial_rows(vec![2, 5]);
+
+        assert_eq!(Some(SolverError::ConflictingRows { first: 2, second: 5 }), builder.clone().try_build().err());
+        assert_eq!(0, builder.build().count());
+
+        let mut builder = SolverBuilder::new();
+        builder.set_rows(rows);
+        builder.set_initial_rows(vec![6]);
+
+        assert_eq!(Some(SolverError::InvalidRow { row: 6 }), builder.try_build().err());
+
+        let mut builder = SolverBuilder::new();
+        builder.add_colored_row(vec![(0, None), (2, Some(0))]);
+        builder.add_colored_row(vec![(0, None), (1, None), (2, Some(0))]);
+        builder.add_row(vec![1]);
+        builder.set_secondary_columns(vec![2]);
+        builder.set_column_bounds(0, 2..=2);
+        builder.set_initial_rows(vec![0, 1]);
+
+        assert_eq!(vec![vec![0, 1]], builder.try_build().unwrap().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_initial_rows_cover_every_column() {
+        let mut builder = SolverBuilder::new();
+        builder.set_rows(vec![vec![0], vec![1], vec![0, 1]]);
+        builder.set_initial_rows(vec![0, 1]);
+
+        assert_eq!(vec![vec![0, 1]], builder.build().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_count_solutions() {
+        assert_eq!(Ok(92), n_queens(8).build().count_solutions());
+
+        let mut solver = n_queens(8).build();
+
+        assert_eq!(10, solver.count_solutions_up_to(10));
+        assert_eq!(82, solver.count_solutions_up_to(100));
+        assert_eq!(Ok(0), solver.count_solutions());
+        assert!(solver.is_completed());
+    }
+
+    #[test]
+    fn test_uniqueness() {
+        let rows = vec![
+            vec![0, 1],
+            vec![0, 2],
+            vec![1, 3],
+            vec![2, 3],
+            vec![0, 1, 2],
+            vec![1, 2, 3],
+        ];
+
+        assert_eq!(Ok(Uniqueness::Unique(vec![2])), Solver::new(rows.clone(), vec![0, 2]).uniqueness());
+        assert_eq!(Ok(Uniqueness::Multiple(vec![0, 3], vec![1, 2])), Solver::new(rows.clone(), vec![]).uniqueness());
+        assert_eq!(Ok(Uniqueness::None), Solver::new(rows, vec![0, 1, 2]).uniqueness());
+
+        let mut solver = n_queens(6).build();
+
+        assert!(matches!(solver.uniqueness(), Ok(Uniqueness::Multiple(..))));
+        assert_eq!(Ok(2), solver.count_solutions());
+    }
+
+    #[test]
+    fn test_randomized() {
+        let mut bounded = SolverBuilder::new();
+        bounded.set_rows(vec![
+            vec![0],
+            vec![0, 1],
+            vec![0, 1],
+            vec![1, 2],
+            vec![1],
+            vec![2],
+        ]);
+        bounded.set_column_bounds(0, 1..=3);
+        bounded.set_column_bounds(1, 2..=2);
+        bounded.set_secondary_columns(vec![2]);
+
+        let queens = n_queens(6).build().collect::<Vec<_>>();
+        let bounded_solutions = bounded.clone().build().collect::<Vec<_>>();
+        assert_eq!(11, bounded_solutions.len());
+
+        let mut orders = vec![];
+        for seed in 0..20 {
+            let randomized = n_queens(6).build_randomized(seed).collect::<Vec<_>>();
+
+            assert_eq!(sorted(queens.clone()), sorted(randomized.clone()));
+            assert_eq!(randomized, n_queens(6).build_randomized(seed).collect::<Vec<_>>());
+            orders.push(randomized);
+
+            let mut builder = bounded.clone();
+            builder.set_seed(seed);
+            assert_eq!(sorted(bounded_solutions.clone()), sorted(builder.build()));
+        }
+
+        assert!(orders.iter().any(|order| *order != queens));
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut solver = n_queens(4).build();
+
+        assert_eq!(Ok(2), solver.count_solutions());
+
+        let stats = solver.stats().clone();
+        assert_eq!(vec![4, 6, 4, 2], stats.nodes_per_depth);
+        assert_eq!(vec![0, 0, 0, 0, 2], stats.solutions_per_depth);
+        assert_eq!(4, stats.max_depth);
+        // Every row that was tried has been removed again
+        assert_eq!(stats.nodes(), stats.backtracks);
+        assert!(stats.updates > 0);
+
+        solver.reset_stats();
+
+        assert_eq!(SearchStats::default(), *solver.stats());
+    }
+
+    #[test]
+    fn test_sparse_columns() {
+        #[derive(Debug, Default, Clone)]
+        struct Recorder(Vec<usize>);
+
+        impl ColumnChooser for Recorder {
+            fn choose(&mut self, columns: Columns<'_>) -> Option<Column> {
+                self.0.extend(columns.map(|column| column.index()));
+                None
+            }
+        }
+
+        let sparse = |col_idx: usize| col_idx * 1_000_000_007 + 3;
+
+        let dense = n_queens(6);
+        let mut builder = SolverBuilder::new();
+        for row in &dense.rows {
+            builder.add_row(row.iter().map(|(col_idx, _)| sparse(*col_idx)).collect());
+        }
+        builder.set_secondary_columns(dense.secondary_columns.iter().copied().map(sparse).collect());
+
+        let solver = builder.clone().build_with_chooser(Recorder::default());
+        // Ranks, files and the secondary columns, including the diagonals that no row contains
+        assert_eq!(6 + 6 + 30, solver.state.column_sizes.len());
+        assert_eq!(sparse(0), solver.chooser.0[0]);
+        assert_eq!(dense.clone().build_with_chooser(FirstColumn).collect::<Vec<_>>(), solver.collect::<Vec<_>>());
+
+        let sharp = [sparse(5)];
+        assert_eq!(
+            dense.clone().build_with_chooser(Sharp::new(&[5])).collect::<Vec<_>>(),
+            builder.clone().build_with_chooser(Sharp::new(&sharp)).collect::<Vec<_>>()
+        );
+
+        let priorities = [(sparse(4), 3), (sparse(10), 5)];
+        assert_eq!(
+            dense.clone().build_with_chooser(MrvPriority::new(&[(4, 3), (10, 5)])).collect::<Vec<_>>(),
+            builder.clone().build_with_chooser(MrvPriority::new(&priorities)).collect::<Vec<_>>()
+        );
+
+        let mut dense = dense;
+        dense.set_column_bounds(0, 0..=1);
+        dense.set_initial_columns(vec![6]);
+        builder.set_column_bounds(sparse(0), 0..=1);
+        builder.set_initial_columns(vec![sparse(6)]);
+        assert_eq!(dense.build().collect::<Vec<_>>(), builder.build().collect::<Vec<_>>());
+
+        assert_eq!(vec![vec![0]], Solver::new(vec![vec![usize::MAX]], vec![]).collect::<Vec<_>>());
+    }
 }
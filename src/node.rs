@@ -1,6 +1,7 @@
+use std::ops::{Index, IndexMut};
 
 #[derive(Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq)]
-pub(crate) struct NodeId(usize);
+pub(crate) struct NodeId(u32);
 
 impl Default for NodeId {
     fn default() -> Self {
@@ -10,11 +11,14 @@ impl Default for NodeId {
 
 impl NodeId {
     pub fn new(value: usize) -> Self {
-        Self(value)
+        match u32::try_from(value) {
+            Ok(value) if value != u32::MAX => Self(value),
+            _ => panic!("too many nodes"),
+        }
     }
 
     pub const fn invalid() -> Self {
-        Self(usize::MAX)
+        Self(u32::MAX)
     }
 
     pub fn is_valid(&self) -> bool {
@@ -22,19 +26,111 @@ impl NodeId {
     }
 
     pub fn value(&self) -> usize {
-        assert!(self.is_valid());
+        debug_assert!(self.is_valid());
 
-        self.0
+        self.0 as usize
     }
 }
 
-#[derive(Default, Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq)]
-pub(crate) struct Node {
-    pub(crate) left: NodeId,
-    pub(crate) right: NodeId,
-    pub(crate) up: NodeId,
-    pub(crate) down: NodeId,
-    pub(crate) header: NodeId,
-    pub(crate) row: isize,
-    pub(crate) col: usize,
-}
\ No newline at end of file
+/// Color of a node that does not assign a color to its column
+pub(crate) const NO_COLOR: i32 = 0;
+
+/// Color of a node whose column has already been purified with the same color
+pub(crate) const PURIFIED: i32 = -1;
+
+/// Largest color that a node can store
+pub(crate) const MAX_COLOR: usize = i32::MAX as usize - 1;
+
+/// Returns the color stored for a node that assigns the given color, if any, to its column.
+/// Panics if the color is above [`MAX_COLOR`], since it could not be told apart from the others.
+pub(crate) fn color_code(color: Option<usize>) -> i32 {
+    color.map_or(NO_COLOR, |color| {
+        assert!(color <= MAX_COLOR, "color {} is too large", color);
+        color as i32 + 1
+    })
+}
+
+/// Row of a node that is a column header or the root of the header ring
+const NO_ROW: u32 = u32::MAX;
+
+/// Values of one field of every node, indexed by node
+#[derive(Default, Debug, Clone)]
+pub(crate) struct NodeField<T>(Vec<T>);
+
+impl<T> Index<NodeId> for NodeField<T> {
+    type Output = T;
+
+    fn index(&self, id: NodeId) -> &T {
+        &self.0[id.value()]
+    }
+}
+
+impl<T> IndexMut<NodeId> for NodeField<T> {
+    fn index_mut(&mut self, id: NodeId) -> &mut T {
+        &mut self.0[id.value()]
+    }
+}
+
+/// Nodes of the matrix, with each field in an array of its own so that following the links
+/// of a column only reads the links. Column headers and the root of the header ring are
+/// nodes without a row in the same arrays. A new solver allocates them before the nodes
+/// of the rows, but columns added later get their headers after the nodes already there.
+///
+/// Headers share the arrays because they are linked into the same rings as the nodes:
+/// each column is a ring of its header and its nodes, so a link can lead to either one
+/// and the loops over a column stop when they reach the header again, without checking
+/// which kind of node a link leads to.
+#[derive(Default, Debug, Clone)]
+pub(crate) struct Nodes {
+    pub(crate) left: NodeField<NodeId>,
+    pub(crate) right: NodeField<NodeId>,
+    pub(crate) up: NodeField<NodeId>,
+    pub(crate) down: NodeField<NodeId>,
+    pub(crate) color: NodeField<i32>,
+    cols: Vec<u32>,
+    rows: Vec<u32>,
+}
+
+impl Nodes {
+    pub fn len(&self) -> usize {
+        self.cols.len()
+    }
+
+    /// Adds a node linked to itself in both directions
+    pub fn push(&mut self, row: Option<usize>, col: usize, color: i32) -> NodeId {
+        let id = NodeId::new(self.len());
+
+        self.left.0.push(id);
+        self.right.0.push(id);
+        self.up.0.push(id);
+        self.down.0.push(id);
+        self.color.0.push(color);
+        self.cols
+            .push(u32::try_from(col).expect("too many columns"));
+        self.rows
+            .push(row.map_or(NO_ROW, |row| u32::try_from(row).expect("too many rows")));
+
+        id
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        self.left.0.reserve(additional);
+        self.right.0.reserve(additional);
+        self.up.0.reserve(additional);
+        self.down.0.reserve(additional);
+        self.color.0.reserve(additional);
+        self.cols.reserve(additional);
+        self.rows.reserve(additional);
+    }
+
+    /// Returns the index of the column of the node
+    pub fn col(&self, id: NodeId) -> usize {
+        self.cols[id.value()] as usize
+    }
+
+    /// Returns the row of the node, or `None` if the node is a column header
+    pub fn row(&self, id: NodeId) -> Option<usize> {
+        let row = self.rows[id.value()];
+        (row != NO_ROW).then_some(row as usize)
+    }
+}
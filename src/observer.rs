@@ -0,0 +1,190 @@
+/// Receives the events of a search as they happen, for example to animate how dancing
+/// links work. Columns and rows are given by their ids in the input.
+///
+/// Every method does nothing by default. A solver without an observer uses [`NoObserver`],
+/// whose calls are compiled away, so observing costs nothing unless it is used.
+///
+/// ```
+/// use algx::{Mrv, Observer, SolverBuilder};
+///
+/// #[derive(Default)]
+/// struct Depth {
+///     current: usize,
+///     max: usize,
+/// }
+///
+/// impl Observer for Depth {
+///     fn step_forward(&mut self, _column: usize, _row: Option<usize>) {
+///         self.current += 1;
+///         self.max = self.max.max(self.current);
+///     }
+///
+///     fn step_backward(&mut self, _column: usize, _row: Option<usize>) {
+///         self.current -= 1;
+///     }
+/// }
+///
+/// let mut builder = SolverBuilder::new();
+/// builder.set_rows(vec![vec![0, 1], vec![0], vec![1], vec![2]]);
+///
+/// let mut solver = builder.build_with_observer(Mrv, Depth::default());
+/// assert_eq!(2, solver.by_ref().count());
+/// assert_eq!(3, solver.observer().max);
+/// ```
+#[allow(unused_variables)]
+pub trait Observer {
+    /// The column was removed from the columns left to cover, along with the rows
+    /// that contain it from their other columns
+    fn cover(&mut self, column: usize) {}
+
+    /// The column and its rows were put back, reverting [`cover`](Self::cover)
+    fn uncover(&mut self, column: usize) {}
+
+    /// A row of the partial solution assigns the color to the column, so the rows that
+    /// assign it a different color were removed
+    fn purify(&mut self, column: usize, color: usize) {}
+
+    /// The rows removed by [`purify`](Self::purify) were put back
+    fn unpurify(&mut self, column: usize, color: usize) {}
+
+    /// The search chose to branch on the column, which has the given number of rows left
+    fn choose_column(&mut self, column: usize, size: usize) {}
+
+    /// The row was added to the partial solution to cover the column, or the column
+    /// was left uncovered if the row is `None`, since its lower bound had been reached.
+    ///
+    /// When the column has bounds, the rows tried so far are left out of the column until
+    /// the search is done with it, and leaving the column uncovered takes it out of the
+    /// columns left to choose. Neither is reported as a [`cover`](Self::cover).
+    fn step_forward(&mut self, column: usize, row: Option<usize>) {}
+
+    /// The row was removed from the partial solution to try the next row of the column,
+    /// or the column was covered again if the row is `None`
+    fn step_backward(&mut self, column: usize, row: Option<usize>) {}
+
+    /// The rows form a solution
+    fn solution(&mut self, rows: &[usize]) {}
+}
+
+/// Observer that ignores every event
+#[derive(Debug, Default, Copy, Clone)]
+pub struct NoObserver;
+
+impl Observer for NoObserver {}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod tests {
+    use super::*;
+    use crate::tests::n_queens;
+    use crate::{Mrv, SolverBuilder};
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum Event {
+        Cover(usize),
+        Uncover(usize),
+        Purify(usize, usize),
+        Unpurify(usize, usize),
+        Choose(usize, usize),
+        Forward(usize, Option<usize>),
+        Backward(usize, Option<usize>),
+        Solution(Vec<usize>),
+    }
+
+    #[derive(Debug, Default)]
+    struct Recorder(Vec<Event>);
+
+    impl Observer for Recorder {
+        fn cover(&mut self, column: usize) { self.0.push(Event::Cover(column)); }
+        fn uncover(&mut self, column: usize) { self.0.push(Event::Uncover(column)); }
+        fn purify(&mut self, column: usize, color: usize) { self.0.push(Event::Purify(column, color)); }
+        fn unpurify(&mut self, column: usize, color: usize) { self.0.push(Event::Unpurify(column, color)); }
+        fn choose_column(&mut self, column: usize, size: usize) { self.0.push(Event::Choose(column, size)); }
+        fn step_forward(&mut self, column: usize, row: Option<usize>) { self.0.push(Event::Forward(column, row)); }
+        fn step_backward(&mut self, column: usize, row: Option<usize>) { self.0.push(Event::Backward(column, row)); }
+        fn solution(&mut self, rows: &[usize]) { self.0.push(Event::Solution(rows.to_vec())); }
+    }
+
+    #[test]
+    fn test_events() {
+        let mut builder = SolverBuilder::new();
+        builder.set_rows(vec![vec![0, 1], vec![0], vec![1]]);
+
+        let mut solver = builder.build_with_observer(Mrv, Recorder::default());
+        assert_eq!(2, solver.by_ref().count());
+
+        use Event::*;
+        assert_eq!(&vec![
+            Choose(0, 2), Cover(0),
+            Cover(1), Forward(0, Some(0)), Solution(vec![0]), Uncover(1), Backward(0, Some(0)),
+            Forward(0, Some(1)), Choose(1, 1), Cover(1),
+            Forward(1, Some(2)), Solution(vec![1, 2]), Backward(1, Some(2)), Uncover(1),
+            Backward(0, Some(1)), Uncover(0),
+        ], &solver.observer().0);
+    }
+
+    #[test]
+    fn test_colors() {
+        let mut builder = SolverBuilder::new();
+        builder.add_colored_row(vec![(0, None), (2, Some(1))]);
+        builder.add_colored_row(vec![(1, None), (2, Some(1))]);
+        builder.add_colored_row(vec![(1, None), (2, Some(0))]);
+        builder.set_secondary_columns(vec![2]);
+
+        let mut solver = builder.build_with_observer(Mrv, Recorder::default());
+        assert_eq!(1, solver.by_ref().count());
+
+        use Event::*;
+        assert_eq!(&vec![
+            Choose(0, 1), Cover(0),
+            Purify(2, 1), Forward(0, Some(0)), Choose(1, 1), Cover(1),
+            Forward(1, Some(1)), Solution(vec![0, 1]), Backward(1, Some(1)), Uncover(1),
+            Unpurify(2, 1), Backward(0, Some(0)), Uncover(0),
+        ], &solver.observer().0);
+    }
+
+    #[test]
+    fn test_whole_search() {
+        let mut builder = n_queens(6);
+        builder.set_column_bounds(0, 0..=1);
+        builder.set_seed(23);
+
+        let mut solver = builder.clone().build_with_observer(Mrv, Recorder::default());
+        let solutions = solver.by_ref().collect::<Vec<_>>();
+        assert_eq!(builder.build().collect::<Vec<_>>(), solutions);
+
+        let events = &solver.observer().0;
+        let count = |f: fn(&Event) -> bool| events.iter().filter(|event| f(event)).count() as u64;
+
+        assert_eq!(solver.stats().nodes(), count(|event| matches!(event, Event::Forward(..))));
+        assert_eq!(solver.stats().backtracks, count(|event| matches!(event, Event::Backward(..))));
+        assert_eq!(count(|event| matches!(event, Event::Cover(_))), count(|event| matches!(event, Event::Uncover(_))));
+
+        let observed = events.iter().filter_map(|event| match event {
+            Event::Solution(rows) => Some(rows.clone()),
+            _ => None,
+        });
+        assert!(observed.eq(solutions));
+
+        // Every step forward is undone by a step backward on the same column and row
+        let mut entered = vec![];
+        for event in events {
+            match event {
+                Event::Forward(column, row) => entered.push((*column, *row)),
+                Event::Backward(column, row) => assert_eq!(Some((*column, *row)), entered.pop()),
+                _ => {}
+            }
+        }
+        assert!(entered.is_empty());
+    }
+
+    #[test]
+    fn test_from_now_on() {
+        let mut solver = n_queens(6).build();
+        solver.next();
+
+        let mut solver = solver.with_observer(Recorder::default());
+        assert_eq!(3, solver.by_ref().count());
+        assert_eq!(3, solver.observer().0.iter().filter(|event| matches!(event, Event::Solution(_))).count());
+    }
+}
@@ -0,0 +1,377 @@
+use crate::budget::Limits;
+use crate::{CancellationToken, ColumnChooser, Interruption, Mrv, Solver, Step};
+
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Runs the search of a [`Solver`] on multiple threads.
+///
+/// The search tree is split at the first levels into subtrees, each of which is
+/// searched by a clone of the solver. The subtrees are divided between the threads,
+/// and a thread that runs out of them steals from the others.
+#[derive(Debug, Clone)]
+pub struct ParallelSolver<C = Mrv> {
+    solver: Solver<C>,
+    threads: usize,
+    split_depth: usize,
+}
+
+impl<C: ColumnChooser + Clone + Send + 'static> ParallelSolver<C> {
+    /// Creates a parallel solver that continues the search of the given solver
+    pub fn new(solver: Solver<C>) -> Self {
+        Self {
+            solver,
+            threads: thread::available_parallelism().map_or(1, usize::from),
+            split_depth: 3,
+        }
+    }
+
+    /// Sets the number of threads. Defaults to the available parallelism.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    /// Sets how many levels of the search tree are split into subtrees. Deeper splits
+    /// balance the work better, but each subtree holds its own copy of the solver.
+    /// Defaults to 3.
+    pub fn set_split_depth(&mut self, split_depth: usize) {
+        self.split_depth = split_depth;
+    }
+
+    /// Counts the remaining solutions. The budget of the solver applies to each subtree,
+    /// and once any of them is interrupted, the others are stopped and the count is lost.
+    pub fn count_solutions(self) -> Result<u128, Interruption> {
+        let (queues, solutions, stop) = self.start();
+
+        let count = thread::scope(|scope| {
+            let handles = (0..self.threads)
+                .map(|worker| {
+                    let queues = &queues;
+                    let stop = &stop;
+                    scope.spawn(move || {
+                        let mut count = 0;
+                        while let Some(mut solver) = next_subtree(queues, worker) {
+                            match solver.count_solutions() {
+                                Ok(solutions) => count += solutions,
+                                Err(interruption) => {
+                                    stop.interrupt(interruption);
+                                    break;
+                                }
+                            }
+                        }
+                        count
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .sum::<u128>()
+                + solutions.len() as u128
+        });
+
+        stop.interruption().map_or(Ok(count), Err)
+    }
+
+    /// Returns the remaining solutions in no particular order. The budget of the solver
+    /// applies to each subtree, and once any of them is interrupted, the solutions end
+    /// early. See [`ParallelSolutions::interruption`].
+    pub fn solutions(self) -> ParallelSolutions {
+        let (queues, solutions, stop) = self.start();
+        let queues = Arc::new(queues);
+        let stop = Arc::new(stop);
+
+        let (sender, receiver) = mpsc::sync_channel(1024);
+
+        for worker in 0..self.threads {
+            let queues = Arc::clone(&queues);
+            let stop = Arc::clone(&stop);
+            let sender: SyncSender<Vec<usize>> = sender.clone();
+
+            thread::spawn(move || {
+                while let Some(mut solver) = next_subtree(&queues, worker) {
+                    for solution in solver.by_ref() {
+                        if sender.send(solution).is_err() {
+                            return;
+                        }
+                    }
+
+                    if let Some(interruption) = solver.interruption() {
+                        stop.interrupt(interruption);
+                        return;
+                    }
+                }
+            });
+        }
+
+        ParallelSolutions {
+            solutions,
+            receiver,
+            stop,
+        }
+    }
+
+    /// Splits the search and divides the subtrees between the threads. The subtrees
+    /// share a token that stops them all, which is also cancelled along with the token
+    /// of the solver, if it has one.
+    fn start(&self) -> (Queues<C>, Vec<Vec<usize>>, Stop) {
+        let (mut subtrees, solutions) = self.split();
+
+        let token = self
+            .solver
+            .cancellation_token
+            .as_ref()
+            .map_or_else(CancellationToken::new, CancellationToken::child);
+        for subtree in &mut subtrees {
+            subtree.cancellation_token = Some(token.clone());
+        }
+
+        let stop = Stop {
+            token,
+            interruption: Mutex::new(None),
+        };
+
+        (distribute(subtrees, self.threads), solutions, stop)
+    }
+
+    /// Searches the first levels of the tree, returning a solver for each subtree
+    /// below them and the solutions found above them. The budget of the solver applies
+    /// to the subtrees, not to the split itself.
+    fn split(&self) -> (Vec<Solver<C>>, Vec<Vec<usize>>) {
+        if self.split_depth == 0 {
+            return (vec![self.solver.clone()], vec![]);
+        }
+
+        // Interrupting the split would lose the subtrees below the columns not tried yet
+        let mut solver = self.solver.clone();
+        solver.limits = Limits::default();
+        solver.cancellation_token = None;
+
+        let split_depth = solver.depth + self.split_depth;
+
+        let mut subtrees = vec![];
+        let mut solutions = vec![];
+
+        while let Some(solution_found) = solver.advance() {
+            if solution_found {
+                solutions.push(solver.partial_solution.clone());
+            }
+
+            if solver.depth == split_depth {
+                // The column at the split depth was just chosen, so the subtree consists of
+                // the steps for trying its rows and restoring it
+                let mut subtree = solver.clone();
+                subtree.floor = subtree.step_stack.len() - 2;
+                subtree.limits = self.solver.limits;
+                subtrees.push(subtree);
+
+                solver.step_stack.pop();
+                if let Some(Step::Restore(first_id)) = solver.step_stack.pop() {
+                    solver.restore(first_id);
+                }
+            }
+        }
+
+        (subtrees, solutions)
+    }
+}
+
+/// Solutions found by a [`ParallelSolver`]. Dropping this stops the search.
+#[derive(Debug)]
+pub struct ParallelSolutions {
+    solutions: Vec<Vec<usize>>,
+    receiver: Receiver<Vec<usize>>,
+    stop: Arc<Stop>,
+}
+
+impl ParallelSolutions {
+    /// Returns the reason why the search was interrupted, if it was, in which case
+    /// the solutions end before all of them are found
+    pub fn interruption(&self) -> Option<Interruption> {
+        self.stop.interruption()
+    }
+}
+
+impl Drop for ParallelSolutions {
+    fn drop(&mut self) {
+        self.stop.token.cancel();
+    }
+}
+
+impl Iterator for ParallelSolutions {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.solutions.pop().or_else(|| self.receiver.recv().ok())
+    }
+}
+
+/// Stops every subtree once one of them is interrupted, and remembers why
+#[derive(Debug)]
+struct Stop {
+    token: CancellationToken,
+    interruption: Mutex<Option<Interruption>>,
+}
+
+impl Stop {
+    fn interrupt(&self, interruption: Interruption) {
+        // The first interruption cancels the others, so it is the one to report
+        self.interruption
+            .lock()
+            .unwrap()
+            .get_or_insert(interruption);
+        self.token.cancel();
+    }
+
+    fn interruption(&self) -> Option<Interruption> {
+        *self.interruption.lock().unwrap()
+    }
+}
+
+type Queues<C> = Vec<Mutex<VecDeque<Solver<C>>>>;
+
+fn distribute<C>(subtrees: Vec<Solver<C>>, threads: usize) -> Queues<C> {
+    let mut queues = (0..threads).map(|_| VecDeque::new()).collect::<Vec<_>>();
+
+    for (i, subtree) in subtrees.into_iter().enumerate() {
+        queues[i % threads].push_back(subtree);
+    }
+
+    queues.into_iter().map(Mutex::new).collect()
+}
+
+/// Takes the next subtree from the worker's own queue, or steals one from the back
+/// of another worker's queue
+fn next_subtree<C>(queues: &Queues<C>, worker: usize) -> Option<Solver<C>> {
+    let own = queues[worker].lock().unwrap().pop_front();
+    if own.is_some() {
+        return own;
+    }
+
+    (1..queues.len()).find_map(|offset| {
+        let victim = (worker + offset) % queues.len();
+        queues[victim].lock().unwrap().pop_back()
+    })
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod tests {
+    use crate::tests::{n_queens, sorted};
+    use crate::{CancellationToken, Interruption, ParallelSolver, SearchBudget};
+
+    #[test]
+    fn test_count_solutions() {
+        for threads in 1..=4 {
+            for split_depth in 0..=5 {
+                let mut solver = ParallelSolver::new(n_queens(8).build());
+                solver.set_threads(threads);
+                solver.set_split_depth(split_depth);
+
+                assert_eq!(Ok(92), solver.count_solutions());
+            }
+        }
+    }
+
+    #[test]
+    fn test_same_solutions() {
+        let expected = sorted(n_queens(6).build());
+
+        for split_depth in 0..=4 {
+            let mut solver = ParallelSolver::new(n_queens(6).build());
+            solver.set_threads(3);
+            solver.set_split_depth(split_depth);
+
+            assert_eq!(expected, sorted(solver.solutions()));
+        }
+    }
+
+    #[test]
+    fn test_randomized() {
+        let expected = sorted(n_queens(7).build());
+
+        for seed in 0..4 {
+            let mut solver = ParallelSolver::new(n_queens(7).build_randomized(seed));
+            solver.set_threads(2);
+
+            assert_eq!(expected, sorted(solver.solutions()));
+        }
+    }
+
+    #[test]
+    fn test_column_bounds() {
+        let mut builder = crate::SolverBuilder::new();
+        builder.set_rows(vec![vec![0], vec![0], vec![0], vec![0, 1], vec![1], vec![1]]);
+        builder.set_column_bounds(0, 1..=2);
+        builder.set_column_bounds(1, 1..=2);
+
+        let expected = builder.clone().build().count_solutions().unwrap();
+
+        for split_depth in 0..=3 {
+            let mut solver = ParallelSolver::new(builder.clone().build());
+            solver.set_split_depth(split_depth);
+
+            assert_eq!(Ok(expected), solver.count_solutions());
+        }
+    }
+
+    #[test]
+    fn test_split_with_budget() {
+        let mut solver = n_queens(8).build();
+        let mut budget = SearchBudget::new();
+        budget.set_max_steps(1);
+        solver.set_budget(budget);
+
+        let mut solver = ParallelSolver::new(solver);
+        solver.set_split_depth(3);
+        let (subtrees, solutions) = solver.split();
+
+        let mut count = solutions.len() as u128;
+        for mut subtree in subtrees {
+            assert_eq!(Err(Interruption::StepLimit), subtree.count_solutions());
+            assert_eq!(Some(Interruption::StepLimit), subtree.interruption());
+
+            subtree.set_budget(SearchBudget::new());
+            count += subtree.count_solutions().unwrap();
+        }
+        assert_eq!(92, count);
+    }
+
+    #[test]
+    fn test_interruption() {
+        let mut solver = n_queens(8).build();
+        let mut budget = SearchBudget::new();
+        budget.set_max_steps(50);
+        solver.set_budget(budget);
+
+        let mut parallel = ParallelSolver::new(solver.clone());
+        parallel.set_threads(2);
+        assert_eq!(Err(Interruption::StepLimit), parallel.count_solutions());
+
+        let mut parallel = ParallelSolver::new(solver);
+        parallel.set_threads(2);
+        let mut solutions = parallel.solutions();
+        assert!(solutions.by_ref().count() < 92);
+        assert_eq!(Some(Interruption::StepLimit), solutions.interruption());
+    }
+
+    #[test]
+    fn test_cancellation() {
+        let token = CancellationToken::new();
+        let mut solver = n_queens(8).build();
+        solver.set_cancellation_token(token.clone());
+
+        let mut solutions = ParallelSolver::new(solver.clone()).solutions();
+        assert!(solutions.next().is_some());
+        drop(solutions);
+        assert!(!token.is_cancelled());
+
+        token.cancel();
+        let mut solutions = ParallelSolver::new(solver).solutions();
+        assert_eq!(0, solutions.by_ref().count());
+        assert_eq!(Some(Interruption::Cancelled), solutions.interruption());
+    }
+}
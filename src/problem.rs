@@ -0,0 +1,288 @@
+use crate::{Solver, SolverBuilder, SolverError};
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::RangeInclusive;
+
+/// Builder for an exact cover problem whose items and options are labeled with
+/// values of any type, instead of column and row indices.
+///
+/// Items are assigned columns in the order they are first used, and solutions
+/// are returned as references to the labels of the options.
+///
+/// ```
+/// use algx::Problem;
+///
+/// let mut problem = Problem::new();
+/// problem.add_option("AB", ['A', 'B']);
+/// problem.add_option("C", ['C']);
+/// problem.add_option("BC", ['B', 'C']);
+/// problem.add_option("A", ['A']);
+///
+/// let mut solutions = problem.solutions().collect::<Vec<_>>();
+/// solutions.sort();
+///
+/// assert_eq!(solutions, vec![vec![&"A", &"BC"], vec![&"AB", &"C"]]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Problem<I, O> {
+    builder: SolverBuilder,
+    items: Vec<I>,
+    item_indices: HashMap<I, usize>,
+    options: Vec<O>,
+}
+
+impl<I: Hash + Eq + Clone, O> Problem<I, O> {
+    pub fn new() -> Self {
+        Self {
+            builder: SolverBuilder::new(),
+            items: vec![],
+            item_indices: HashMap::new(),
+            options: vec![],
+        }
+    }
+
+    /// Returns the column of the item, adding the item if it has not been used yet
+    fn item_index(&mut self, item: I) -> usize {
+        if let Some(&col_idx) = self.item_indices.get(&item) {
+            return col_idx;
+        }
+
+        self.items.push(item.clone());
+        self.item_indices.insert(item, self.items.len() - 1);
+        self.items.len() - 1
+    }
+
+    /// Adds an option that covers the given items
+    pub fn add_option(&mut self, option: O, items: impl IntoIterator<Item = I>) {
+        self.add_colored_option(option, items.into_iter().map(|item| (item, None)));
+    }
+
+    /// Adds an option whose items may be assigned a color.
+    /// See [`SolverBuilder::add_colored_row`].
+    pub fn add_colored_option(
+        &mut self,
+        option: O,
+        items: impl IntoIterator<Item = (I, Option<usize>)>,
+    ) {
+        let mut row = items
+            .into_iter()
+            .map(|(item, color)| (self.item_index(item), color))
+            .collect::<Vec<_>>();
+        row.sort_unstable_by_key(|(col_idx, _)| *col_idx);
+
+        self.builder.add_colored_row(row);
+        self.options.push(option);
+    }
+
+    /// Makes the item secondary, so that it is covered at most once instead of exactly once
+    pub fn add_secondary_item(&mut self, item: I) {
+        let col_idx = self.item_index(item);
+        self.builder.secondary_columns.push(col_idx);
+    }
+
+    /// Sets how many times the item must be covered.
+    /// See [`SolverBuilder::set_column_bounds`].
+    pub fn set_item_bounds(&mut self, item: I, bounds: RangeInclusive<usize>) {
+        let col_idx = self.item_index(item);
+        self.builder.set_column_bounds(col_idx, bounds);
+    }
+
+    /// Returns the item of the column
+    pub fn item(&self, col_idx: usize) -> Option<&I> {
+        self.items.get(col_idx)
+    }
+
+    /// Returns the column of the item
+    pub fn column(&self, item: &I) -> Option<usize> {
+        self.item_indices.get(item).copied()
+    }
+
+    /// Returns the option of the row
+    pub fn option(&self, row_idx: usize) -> Option<&O> {
+        self.options.get(row_idx)
+    }
+
+    pub fn items(&self) -> &[I] {
+        &self.items
+    }
+
+    pub fn options(&self) -> &[O] {
+        &self.options
+    }
+
+    /// Returns the builder of the underlying problem of columns and rows
+    pub fn builder(&self) -> &SolverBuilder {
+        &self.builder
+    }
+
+    /// Returns the builder of the underlying problem of columns and rows, for example
+    /// to set a seed or the initial rows
+    pub fn builder_mut(&mut self) -> &mut SolverBuilder {
+        &mut self.builder
+    }
+
+    /// Returns the solutions as the options they consist of
+    pub fn solutions(&self) -> ProblemSolutions<'_, O> {
+        ProblemSolutions {
+            options: &self.options,
+            solver: self.builder.clone().build(),
+        }
+    }
+
+    /// Returns the solutions after checking that the problem is valid
+    pub fn try_solutions(&self) -> Result<ProblemSolutions<'_, O>, SolverError> {
+        Ok(ProblemSolutions {
+            options: &self.options,
+            solver: self.builder.clone().try_build()?,
+        })
+    }
+
+    /// Maps the rows of a solution found by a solver of [`builder`](Self::builder)
+    /// to their options
+    pub fn solution_options(&self, solution: &[usize]) -> Vec<&O> {
+        solution
+            .iter()
+            .map(|&row_idx| &self.options[row_idx])
+            .collect()
+    }
+}
+
+impl<I: Hash + Eq + Clone, O: Hash + Eq> Problem<I, O> {
+    /// Sets the options that are part of every solution.
+    /// See [`SolverBuilder::set_initial_rows`].
+    ///
+    /// Returns an error and leaves the initial options as they are if one of the options
+    /// has not been added. An option added more than once refers to the first one.
+    pub fn set_initial_options(&mut self, options: &[O]) -> Result<(), SolverError> {
+        let mut row_indices = HashMap::with_capacity(self.options.len());
+        for (row_idx, option) in self.options.iter().enumerate() {
+            row_indices.entry(option).or_insert(row_idx);
+        }
+
+        let initial_rows = options
+            .iter()
+            .enumerate()
+            .map(|(index, option)| {
+                row_indices
+                    .get(option)
+                    .copied()
+                    .ok_or(SolverError::UnknownOption { index })
+            })
+            .collect::<Result<_, _>>()?;
+
+        self.builder.set_initial_rows(initial_rows);
+        Ok(())
+    }
+}
+
+impl<I: Hash + Eq + Clone, O> Default for Problem<I, O> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Iterator over the solutions of a [`Problem`]
+#[derive(Debug, Clone)]
+pub struct ProblemSolutions<'a, O> {
+    options: &'a [O],
+    solver: Solver,
+}
+
+impl<'a, O> ProblemSolutions<'a, O> {
+    /// Returns the underlying solver, for example to set a budget or read the statistics
+    pub fn solver(&mut self) -> &mut Solver {
+        &mut self.solver
+    }
+}
+
+impl<'a, O> Iterator for ProblemSolutions<'a, O> {
+    type Item = Vec<&'a O>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let solution = self.solver.next()?;
+
+        Some(
+            solution
+                .into_iter()
+                .map(|row_idx| &self.options[row_idx])
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod tests {
+    use crate::tests::sorted;
+    use crate::{Problem, SolverError};
+
+    #[test]
+    fn test_n_queens() {
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        enum Item { Rank(usize), File(usize), Diagonal(usize), AntiDiagonal(usize) }
+
+        let n = 6;
+        let mut problem = Problem::new();
+
+        for rank in 0..n {
+            for file in 0..n {
+                problem.add_option((rank, file), [
+                    Item::Rank(rank),
+                    Item::File(file),
+                    Item::Diagonal(rank + file),
+                    Item::AntiDiagonal(n - 1 - rank + file),
+                ]);
+            }
+        }
+        for i in 0..2 * n - 1 {
+            problem.add_secondary_item(Item::Diagonal(i));
+            problem.add_secondary_item(Item::AntiDiagonal(i));
+        }
+
+        assert_eq!(Some(4), problem.column(&Item::File(1)));
+        assert_eq!(Some(&Item::Diagonal(0)), problem.item(2));
+        assert_eq!(Some(&(1, 1)), problem.option(7));
+
+        let solutions = sorted(problem.solutions());
+        assert_eq!(vec![
+            vec![&(0, 1), &(1, 3), &(2, 5), &(3, 0), &(4, 2), &(5, 4)],
+            vec![&(0, 2), &(1, 5), &(2, 1), &(3, 4), &(4, 0), &(5, 3)],
+            vec![&(0, 3), &(1, 0), &(2, 4), &(3, 1), &(4, 5), &(5, 2)],
+            vec![&(0, 4), &(1, 2), &(2, 0), &(3, 5), &(4, 3), &(5, 1)],
+        ], solutions);
+
+        problem.set_initial_options(&[(0, 1)]).unwrap();
+        assert_eq!(1, problem.solutions().count());
+
+        assert_eq!(Err(SolverError::UnknownOption { index: 1 }), problem.set_initial_options(&[(0, 2), (6, 0)]));
+        assert_eq!(vec![1], problem.builder().initial_rows);
+    }
+
+    #[test]
+    fn test_bounds_and_colors() {
+        let mut problem = Problem::new();
+        problem.add_colored_option("a", [("x", None), ("s", Some(0))]);
+        problem.add_colored_option("b", [("x", None), ("s", Some(0))]);
+        problem.add_colored_option("c", [("x", None), ("s", Some(1))]);
+        problem.add_secondary_item("s");
+        problem.set_item_bounds("x", 2..=2);
+
+        assert_eq!(vec![vec![&"a", &"b"]], sorted(problem.solutions()));
+        assert_eq!(vec![&"c", &"a"], problem.solution_options(&[2, 0]));
+
+        // Items with bounds must be covered even if no option contains them
+        problem.set_item_bounds("y", 1..=1);
+        assert_eq!(0, problem.solutions().count());
+        problem.set_item_bounds("y", 0..=1);
+        assert_eq!(1, problem.solutions().count());
+    }
+
+    #[test]
+    fn test_try_solutions() {
+        let mut problem = Problem::new();
+        problem.add_option('a', [1, 2, 1]);
+
+        assert_eq!(Some(SolverError::DuplicateColumn { row: 0, column: 0 }), problem.try_solutions().err());
+    }
+}
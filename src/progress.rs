@@ -0,0 +1,244 @@
+use crate::node::NodeId;
+use crate::{ColumnChooser, NoObserver, Observer, Solver, Step};
+
+/// How far the search has got, reported by [`Solver::progress`]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Progress {
+    /// Number of steps taken so far
+    pub steps: u64,
+    /// Number of columns currently branched on
+    pub depth: usize,
+    /// Number of solutions found so far
+    pub solutions: u64,
+    /// Index of the row being tried and the number of rows, on each of the top levels
+    /// of the search. Leaving a column uncovered once its lower bound is reached counts
+    /// as its last row.
+    pub branches: Vec<(usize, usize)>,
+    /// Estimated fraction of the search that is done, from 0 to 1. The subtrees of the rows
+    /// on each level are assumed to be of the same size, which they rarely are, so the
+    /// fraction tends to move unevenly. Columns with bounds can make it move backwards,
+    /// as the number of their rows is not known exactly.
+    pub fraction: f64,
+}
+
+impl<C: ColumnChooser, O: Observer> Solver<C, O> {
+    /// Returns how far the search has got, with the branches of up to the given number
+    /// of top levels. Takes time in proportion to the number of rows on those levels,
+    /// so it is meant to be called every once in a while, like every million steps.
+    pub fn progress(&self, levels: usize) -> Progress {
+        let mut branches: Vec<(usize, usize)> = vec![];
+        let mut steps = self.step_stack.iter().copied().peekable();
+
+        while branches.len() < levels {
+            let Some(step) = steps.next() else {
+                break;
+            };
+            let Step::Restore(first_id) = step else {
+                continue;
+            };
+
+            let rows = self.level_rows(branches.len(), first_id);
+
+            let (index, total) = match steps.peek().copied() {
+                Some(Step::Forward(node_id) | Step::Backward(node_id)) => {
+                    let header = self.state.header_of(node_id);
+                    let index = rows
+                        .iter()
+                        .position(|id| *id == node_id)
+                        .unwrap_or(rows.len());
+                    (index, rows.len() + usize::from(node_id == header))
+                }
+                _ => (rows.len(), rows.len()),
+            };
+
+            // The subtree of the row is done once the search is about to backtrack from it
+            let index = match steps.clone().nth(1) {
+                None if matches!(steps.peek(), Some(Step::Backward(_))) => index + 1,
+                _ => index,
+            };
+
+            branches.push((index, total));
+        }
+
+        // Levels below one without rows can not be reached
+        let reachable = branches
+            .iter()
+            .position(|(_, total)| *total == 0)
+            .unwrap_or(branches.len());
+
+        let mut fraction = 0.0;
+        for (index, total) in branches[..reachable].iter().rev().copied() {
+            fraction = (index as f64 + fraction) / total as f64;
+        }
+
+        if self.is_completed() {
+            fraction = 1.0;
+        }
+
+        Progress {
+            steps: self.steps,
+            depth: self.depth,
+            solutions: self.state.stats.solutions(),
+            branches,
+            fraction: fraction.min(1.0),
+        }
+    }
+
+    /// Rows of the column branched on at the given level, in the order they are tried
+    fn level_rows(&self, level: usize, first_id: NodeId) -> Vec<NodeId> {
+        if self.rng.is_some() {
+            let Some(shuffled) = self.shuffled_levels.get(level) else {
+                return vec![];
+            };
+            let end = self
+                .shuffled_levels
+                .get(level + 1)
+                .map_or(self.shuffled_rows.len(), |next| next.start);
+
+            return self.shuffled_rows[shuffled.start..end].to_vec();
+        }
+
+        // Rows that have been tried keep their links to the rows after them
+        let header = self.state.header_of(first_id);
+        let mut rows = vec![];
+
+        let mut node_id = first_id;
+        while node_id != header {
+            rows.push(node_id);
+            node_id = self.state.nodes.down[node_id];
+        }
+
+        rows
+    }
+
+    /// Returns an iterator over the remaining solutions that calls `callback` with
+    /// the [`progress`](Self::progress) of the search every `interval` steps.
+    ///
+    /// ```
+    /// use algx::Solver;
+    ///
+    /// let rows = vec![vec![0, 1], vec![0], vec![1], vec![1, 2], vec![2], vec![0, 2]];
+    /// let mut fractions = vec![];
+    ///
+    /// let solutions = Solver::new(rows, vec![])
+    ///     .with_progress(2, 3, |progress| fractions.push(progress.fraction))
+    ///     .count();
+    ///
+    /// assert_eq!(4, solutions);
+    /// assert!(fractions.windows(2).all(|pair| pair[0] <= pair[1]));
+    /// ```
+    pub fn with_progress<F: FnMut(&Progress)>(
+        self,
+        interval: u64,
+        levels: usize,
+        callback: F,
+    ) -> ProgressSolutions<C, F, O> {
+        ProgressSolutions {
+            solver: self,
+            interval: interval.max(1),
+            levels,
+            callback,
+        }
+    }
+}
+
+/// Iterator over the solutions of a [`Solver`] that reports the progress of the search,
+/// made by [`Solver::with_progress`]
+#[derive(Debug, Clone)]
+pub struct ProgressSolutions<C, F, O = NoObserver> {
+    solver: Solver<C, O>,
+    interval: u64,
+    levels: usize,
+    callback: F,
+}
+
+impl<C: ColumnChooser, F: FnMut(&Progress), O: Observer> ProgressSolutions<C, F, O> {
+    /// Returns the underlying solver, for example to set a budget or read the statistics
+    pub fn solver(&mut self) -> &mut Solver<C, O> {
+        &mut self.solver
+    }
+
+    pub fn into_solver(self) -> Solver<C, O> {
+        self.solver
+    }
+}
+
+impl<C: ColumnChooser, F: FnMut(&Progress), O: Observer> Iterator for ProgressSolutions<C, F, O> {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(solution_found) = self.solver.advance() {
+            if self.solver.steps.is_multiple_of(self.interval) {
+                (self.callback)(&self.solver.progress(self.levels));
+            }
+
+            if solution_found {
+                return Some(self.solver.partial_solution.clone());
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod tests {
+    use crate::tests::n_queens;
+    use crate::SolverBuilder;
+
+    #[test]
+    fn test_branches() {
+        let mut solver = n_queens(4).build();
+
+        let progress = solver.progress(2);
+        assert_eq!((0, 1, 0), (progress.steps, progress.depth, progress.solutions));
+        assert_eq!(vec![(0, 4)], progress.branches);
+        assert_eq!(0.0, progress.fraction);
+
+        // Queen on the second square of the first rank leads to a solution
+        solver.step();
+        solver.next();
+        let progress = solver.progress(2);
+        assert_eq!(1, progress.solutions);
+        assert_eq!((1, 4), progress.branches[0]);
+        assert!(progress.fraction >= 0.25 && progress.fraction < 0.5);
+
+        solver.count_solutions().unwrap();
+        assert_eq!(1.0, solver.progress(2).fraction);
+    }
+
+    #[test]
+    fn test_fraction_grows() {
+        let mut seeded = n_queens(6);
+        seeded.set_seed(22);
+
+        let mut bounded = SolverBuilder::new();
+        bounded.set_rows(vec![vec![0, 1], vec![0, 2], vec![0], vec![1], vec![1, 2], vec![2, 3], vec![3]]);
+        bounded.set_column_bounds(0, 1..=2);
+        bounded.set_column_bounds(3, 0..=1);
+
+        let mut seeded_bounded = bounded.clone();
+        seeded_bounded.set_seed(22);
+
+        for (builder, monotonic) in [(n_queens(6), true), (seeded, true), (seeded_bounded, false), (bounded, false)] {
+            let mut fractions = vec![];
+            let solutions = builder.clone().build().with_progress(1, 3, |progress| fractions.push(progress.fraction)).count();
+
+            assert_eq!(builder.build().count(), solutions);
+            assert_eq!(Some(&1.0), fractions.last());
+            assert!(fractions.iter().all(|fraction| (0.0..=1.0).contains(fraction)));
+            assert!(!monotonic || fractions.windows(2).all(|pair| pair[0] <= pair[1]), "{:?}", fractions);
+        }
+    }
+
+    #[test]
+    fn test_interval() {
+        let mut calls = vec![];
+        let mut solutions = n_queens(6).build().with_progress(10, 1, |progress| calls.push(progress.steps));
+        assert_eq!(4, solutions.by_ref().count());
+
+        let steps = solutions.into_solver().progress(0).steps;
+        assert_eq!((1..=steps / 10).map(|i| i * 10).collect::<Vec<_>>(), calls);
+    }
+}
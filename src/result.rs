@@ -1,3 +1,169 @@
+use std::fmt;
+
+/// Errors detected in the input of a [`Solver`](crate::Solver)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum SolverError {
+    /// The row has no columns
+    EmptyRow { row: usize },
+    /// The columns of the row are not in ascending order
+    UnsortedColumns { row: usize },
+    /// The row contains the column more than once
+    DuplicateColumn { row: usize, column: usize },
+    /// The row assigns a color to a primary column
+    ColoredPrimaryColumn { row: usize, column: usize },
+    /// The row assigns the column a color above 2^31 - 2
+    ColorTooLarge { row: usize, column: usize },
+    /// The column has an upper bound of zero or a lower bound above its upper bound
+    InvalidColumnBounds { column: usize },
+    /// The column is to be covered initially, but no row contains it
+    UncoverableColumn { column: usize },
+    /// The column is to be covered initially more than once
+    ConflictingCoveredColumn { column: usize },
+    /// The row does not exist
+    InvalidRow { row: usize },
+    /// The rows are both selected initially, but they can not be in the same solution
+    ConflictingRows { first: usize, second: usize },
+    /// The option at the index of the given options is not an option of the
+    /// [`Problem`](crate::Problem)
+    UnknownOption { index: usize },
+}
+
+impl fmt::Display for SolverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyRow { row } => write!(f, "row {} is empty", row),
+            Self::UnsortedColumns { row } => {
+                write!(f, "columns of row {} are not in ascending order", row)
+            }
+            Self::DuplicateColumn { row, column } => {
+                write!(f, "row {} contains column {} more than once", row, column)
+            }
+            Self::ColoredPrimaryColumn { row, column } => {
+                write!(
+                    f,
+                    "row {} assigns a color to primary column {}",
+                    row, column
+                )
+            }
+            Self::ColorTooLarge { row, column } => {
+                write!(
+                    f,
+                    "row {} assigns too large a color to column {}",
+                    row, column
+                )
+            }
+            Self::InvalidColumnBounds { column } => {
+                write!(f, "column {} has invalid bounds", column)
+            }
+            Self::UncoverableColumn { column } => {
+                write!(
+                    f,
+                    "column {} is covered initially, but no row contains it",
+                    column
+                )
+            }
+            Self::ConflictingCoveredColumn { column } => {
+                write!(f, "column {} is covered initially more than once", column)
+            }
+            Self::InvalidRow { row } => write!(f, "row {} does not exist", row),
+            Self::ConflictingRows { first, second } => {
+                write!(
+                    f,
+                    "rows {} and {} can not be in the same solution",
+                    first, second
+                )
+            }
+            Self::UnknownOption { index } => write!(f, "option {} is unknown", index),
+        }
+    }
+}
+
+impl std::error::Error for SolverError {}
+
+/// Errors in a problem written in the DLX text format
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// There is no line listing the items
+    MissingItems,
+    /// The item is listed more than once
+    DuplicateItem { line: usize, item: String },
+    /// The multiplicity of the item is not of the form `v|` or `u:v|` with `u <= v` and `v > 0`
+    InvalidMultiplicity { line: usize, item: String },
+    /// The option contains an item that is not listed
+    UnknownItem { line: usize, item: String },
+    /// The option contains the item more than once
+    DuplicateOptionItem { line: usize, item: String },
+    /// The option assigns a color to a primary item
+    ColoredPrimaryItem { line: usize, item: String },
+}
 
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingItems => write!(f, "no items are listed"),
+            Self::DuplicateItem { line, item } => {
+                write!(f, "line {}: item {} is listed more than once", line, item)
+            }
+            Self::InvalidMultiplicity { line, item } => {
+                write!(
+                    f,
+                    "line {}: item {} has an invalid multiplicity",
+                    line, item
+                )
+            }
+            Self::UnknownItem { line, item } => {
+                write!(f, "line {}: unknown item {}", line, item)
+            }
+            Self::DuplicateOptionItem { line, item } => {
+                write!(
+                    f,
+                    "line {}: option contains item {} more than once",
+                    line, item
+                )
+            }
+            Self::ColoredPrimaryItem { line, item } => {
+                write!(
+                    f,
+                    "line {}: option assigns a color to primary item {}",
+                    line, item
+                )
+            }
+        }
+    }
 }
+
+impl std::error::Error for ParseError {}
+
+/// Errors in the bytes given to [`Solver::resume`](crate::Solver::resume)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// The bytes are not a snapshot of a version this solver can read
+    UnknownFormat,
+    /// The bytes end in the middle of the snapshot
+    UnexpectedEnd,
+    /// A value is out of range, or there are bytes left after the snapshot
+    InvalidValue,
+    /// The problem of the snapshot is not valid
+    InvalidProblem(SolverError),
+    /// The position of the search does not match the problem
+    InvalidPosition,
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownFormat => write!(f, "unknown snapshot format"),
+            Self::UnexpectedEnd => write!(f, "snapshot ends unexpectedly"),
+            Self::InvalidValue => write!(f, "snapshot contains an invalid value"),
+            Self::InvalidProblem(err) => write!(f, "invalid problem in snapshot: {}", err),
+            Self::InvalidPosition => {
+                write!(
+                    f,
+                    "search position does not match the problem of the snapshot"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
@@ -0,0 +1,621 @@
+use crate::node::MAX_COLOR;
+use crate::rng::Rng;
+use crate::{ColumnChooser, Columns, Mrv, Observer, SnapshotError, Solver, SolverBuilder, Step};
+
+/// Start of every snapshot, followed by the version of the format
+const MAGIC: &[u8; 4] = b"algx";
+const VERSION: u8 = 1;
+
+/// How far the search has got, described by rows and columns instead of nodes
+/// so that it can be replayed on a solver built from scratch
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Position {
+    Completed,
+    /// The initial rows cover every primary column and have not been reported yet
+    Selected,
+    /// Columns branched on, from the first one to the current one
+    Branched(Vec<Level>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Level {
+    column: usize,
+    /// Generator before the rows of the column were shuffled
+    rng: Option<Rng>,
+    step: LevelStep,
+}
+
+/// What the search is doing on a level. A row of `None` means leaving the column
+/// uncovered once its lower bound is reached.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum LevelStep {
+    /// The row is tried next
+    Pending(Option<usize>),
+    /// The row is part of the solution, and the levels after this one are below it
+    Entered(Option<usize>),
+    /// Every row has been tried and the column is restored next
+    Exhausted,
+}
+
+/// Snapshots capture the problem and how far the search has got in a compact byte format,
+/// so that a long search can be stopped and continued later, even in another process.
+/// The resumed solver finds the remaining solutions without repeating any or leaving
+/// any out.
+///
+/// ```
+/// use algx::Solver;
+///
+/// let rows = vec![vec![0, 1], vec![0], vec![1], vec![1, 2], vec![2], vec![0, 2]];
+/// let mut solver = Solver::new(rows, vec![]);
+/// let first = solver.next().unwrap();
+///
+/// let bytes = solver.snapshot();
+/// let rest = Solver::resume(&bytes).unwrap().collect::<Vec<_>>();
+///
+/// assert_eq!(solver.collect::<Vec<_>>(), rest);
+/// assert!(!rest.contains(&first));
+/// ```
+impl<C: ColumnChooser, O: Observer> Solver<C, O> {
+    /// Serializes the problem and the position of the search. Assumptions are kept
+    /// as assumptions, while the budget, cancellation token and statistics are left out.
+    ///
+    /// A randomized search continues in the same order, as long as the strategy for
+    /// choosing columns does not depend on its own random state, like
+    /// [`MrvRandom`](crate::MrvRandom) does. The columns already branched on are kept either way.
+    pub fn snapshot(&self) -> Vec<u8> {
+        let mut builder = self.to_builder();
+        builder.set_initial_rows(self.initial.rows.clone());
+
+        let mut writer = Writer(MAGIC.to_vec());
+        writer.byte(VERSION);
+        writer.builder(&builder);
+        writer.list(&self.initial.assumptions);
+        writer.rng(self.rng);
+        writer.position(&self.search_position());
+        writer.0
+    }
+
+    fn search_position(&self) -> Position {
+        let steps = match self.step_stack.as_slice() {
+            [] => return Position::Completed,
+            [Step::Selected] => return Position::Selected,
+            steps => steps,
+        };
+
+        let mut levels: Vec<Level> = vec![];
+        let mut shuffled_levels = self.shuffled_levels.iter();
+
+        for step in steps.iter().copied() {
+            match step {
+                Step::Restore(first_id) => levels.push(Level {
+                    column: self.state.column_id(self.state.nodes.col(first_id)),
+                    rng: shuffled_levels.next().map(|level| level.rng),
+                    step: LevelStep::Exhausted,
+                }),
+                Step::Forward(node_id) => {
+                    if let Some(level) = levels.last_mut() {
+                        level.step = LevelStep::Pending(self.state.nodes.row(node_id));
+                    }
+                }
+                Step::Backward(node_id) => {
+                    if let Some(level) = levels.last_mut() {
+                        level.step = LevelStep::Entered(self.state.nodes.row(node_id));
+                    }
+                }
+                Step::Selected => {}
+            }
+        }
+
+        Position::Branched(levels)
+    }
+
+    /// Takes the search of an unwound solver to the given position by branching on the same
+    /// columns and skipping the rows tried before. Returns false if the position can not
+    /// be reached.
+    fn replay(&mut self, position: &Position) -> bool {
+        if !self.select_initial() {
+            return *position == Position::Completed;
+        }
+
+        let header_root_id = self.state.header;
+        let has_columns = self.state.nodes.right[header_root_id] != header_root_id;
+
+        let levels = match position {
+            Position::Completed => return true,
+            Position::Selected => {
+                self.step_stack.push(Step::Selected);
+                return !has_columns && !self.initial.selected_rows.is_empty();
+            }
+            Position::Branched(levels) => levels,
+        };
+
+        for (i, level) in levels.iter().enumerate() {
+            let target = match level.step {
+                LevelStep::Pending(row) | LevelStep::Entered(row) => Some(row),
+                LevelStep::Exhausted => None,
+            };
+
+            let is_last = i + 1 == levels.len();
+            if !is_last && !matches!(level.step, LevelStep::Entered(_)) {
+                return false;
+            }
+
+            let Some(col_idx) = self.state.find_column(level.column) else {
+                return false;
+            };
+            let column_id = self.state.column_headers[col_idx];
+            if !Columns::new(&self.state).any(|column| column.node_id == column_id) {
+                return false;
+            }
+
+            if level.rng.is_some() != self.rng.is_some() {
+                return false;
+            }
+            if level.rng.is_some() {
+                self.rng = level.rng;
+            }
+
+            self.branch_on(column_id);
+
+            while let Some(Step::Forward(node_id)) = self.step_stack.last().copied() {
+                if target == Some(self.state.nodes.row(node_id)) {
+                    break;
+                }
+
+                self.step_stack.pop();
+                if self.enter(node_id) {
+                    self.step_stack.pop();
+                    self.step_backward(node_id);
+                }
+            }
+
+            match (level.step, self.step_stack.last().copied()) {
+                (LevelStep::Entered(_), Some(Step::Forward(node_id))) => {
+                    self.step_stack.pop();
+                    if !self.enter(node_id) {
+                        return false;
+                    }
+                }
+                (LevelStep::Pending(_), Some(Step::Forward(_)))
+                | (LevelStep::Exhausted, Some(Step::Restore(_))) => {}
+                _ => return false,
+            }
+        }
+
+        true
+    }
+}
+
+impl<C: ColumnChooser> Solver<C> {
+    /// Continues the search of a snapshot, using the given strategy for choosing
+    /// columns in the rest of the search.
+    pub fn resume_with_chooser(bytes: &[u8], chooser: C) -> Result<Self, SnapshotError> {
+        let mut reader = Reader { bytes, offset: 0 };
+
+        if reader.take(MAGIC.len())? != MAGIC || reader.byte()? != VERSION {
+            return Err(SnapshotError::UnknownFormat);
+        }
+
+        let builder = reader.builder()?;
+        let assumptions = reader.list()?;
+        let rng = reader.rng()?;
+        let position = reader.position(rng.is_some())?;
+
+        if reader.offset != bytes.len() {
+            return Err(SnapshotError::InvalidValue);
+        }
+
+        // Initial rows that can not be in the same solution are not an error, as the solver
+        // of the snapshot may have been built without checking them
+        builder
+            .validate_allowing_empty_rows()
+            .map_err(SnapshotError::InvalidProblem)?;
+
+        let mut solver = builder.build_with_chooser(chooser);
+        solver.unwind();
+        solver.initial.assumptions = assumptions;
+        solver.rng = rng;
+
+        if !solver.replay(&position) {
+            return Err(SnapshotError::InvalidPosition);
+        }
+
+        solver.rng = rng;
+        solver.state.stats = Default::default();
+
+        Ok(solver)
+    }
+}
+
+impl Solver {
+    /// Continues the search of a snapshot made with [`Solver::snapshot`]
+    pub fn resume(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        Self::resume_with_chooser(bytes, Mrv)
+    }
+}
+
+/// Writes numbers as LEB128 varints, so that small ones take a single byte
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn byte(&mut self, byte: u8) {
+        self.0.push(byte);
+    }
+
+    fn number(&mut self, mut value: usize) {
+        while value >= 0x80 {
+            self.0.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.0.push(value as u8);
+    }
+
+    fn list(&mut self, values: &[usize]) {
+        self.number(values.len());
+        for value in values {
+            self.number(*value);
+        }
+    }
+
+    fn rng(&mut self, rng: Option<Rng>) {
+        match rng {
+            Some(rng) => {
+                self.byte(1);
+                self.0.extend_from_slice(&rng.state().to_le_bytes());
+            }
+            None => self.byte(0),
+        }
+    }
+
+    fn builder(&mut self, builder: &SolverBuilder) {
+        self.number(builder.rows.len());
+        for row in &builder.rows {
+            self.number(row.len());
+
+            // Columns are in ascending order, so the differences between them are smaller
+            let mut previous = 0;
+            for (column, color) in row {
+                self.number(column - previous);
+                self.number(color.map_or(0, |color| color + 1));
+                previous = *column;
+            }
+        }
+
+        self.list(&builder.columns);
+        self.list(&builder.secondary_columns);
+
+        self.number(builder.column_bounds.len());
+        for (column, bounds) in &builder.column_bounds {
+            self.number(*column);
+            self.number(*bounds.start());
+            self.number(*bounds.end());
+        }
+
+        self.list(&builder.initial_columns);
+        self.list(&builder.initial_rows);
+    }
+
+    fn position(&mut self, position: &Position) {
+        let levels = match position {
+            Position::Completed => return self.byte(0),
+            Position::Selected => return self.byte(1),
+            Position::Branched(levels) => levels,
+        };
+
+        self.byte(2);
+        self.number(levels.len());
+
+        for level in levels {
+            self.number(level.column);
+            if let Some(rng) = level.rng {
+                self.0.extend_from_slice(&rng.state().to_le_bytes());
+            }
+
+            // Rows are shifted by one to make room for the column header
+            match level.step {
+                LevelStep::Pending(row) => {
+                    self.byte(0);
+                    self.number(row.map_or(0, |row| row + 1));
+                }
+                LevelStep::Entered(row) => {
+                    self.byte(1);
+                    self.number(row.map_or(0, |row| row + 1));
+                }
+                LevelStep::Exhausted => self.byte(2),
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        let bytes = self
+            .bytes
+            .get(self.offset..self.offset + len)
+            .ok_or(SnapshotError::UnexpectedEnd)?;
+        self.offset += len;
+
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn number(&mut self) -> Result<usize, SnapshotError> {
+        let mut value = 0usize;
+
+        for shift in (0..usize::BITS).step_by(7) {
+            let byte = self.byte()?;
+            let bits = usize::from(byte & 0x7f);
+
+            if bits.checked_shl(shift).map(|bits| bits >> shift) != Some(bits) {
+                return Err(SnapshotError::InvalidValue);
+            }
+            value |= bits << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(SnapshotError::InvalidValue)
+    }
+
+    fn list(&mut self) -> Result<Vec<usize>, SnapshotError> {
+        let len = self.number()?;
+
+        (0..len).map(|_| self.number()).collect()
+    }
+
+    fn u64(&mut self) -> Result<u64, SnapshotError> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn rng(&mut self) -> Result<Option<Rng>, SnapshotError> {
+        match self.byte()? {
+            0 => Ok(None),
+            1 => Ok(Some(Rng::new(self.u64()?))),
+            _ => Err(SnapshotError::InvalidValue),
+        }
+    }
+
+    fn builder(&mut self) -> Result<SolverBuilder, SnapshotError> {
+        let mut builder = SolverBuilder::new();
+
+        for _ in 0..self.number()? {
+            let mut row = vec![];
+            let mut previous = 0usize;
+
+            for _ in 0..self.number()? {
+                let column = previous
+                    .checked_add(self.number()?)
+                    .ok_or(SnapshotError::InvalidValue)?;
+
+                let color = self.number()?.checked_sub(1);
+                if color.is_some_and(|color| color > MAX_COLOR) {
+                    return Err(SnapshotError::InvalidValue);
+                }
+
+                row.push((column, color));
+                previous = column;
+            }
+
+            builder.add_colored_row(row);
+        }
+
+        for column in self.list()? {
+            builder.add_column(column);
+        }
+        builder.set_secondary_columns(self.list()?);
+
+        for _ in 0..self.number()? {
+            let column = self.number()?;
+            let (min, max) = (self.number()?, self.number()?);
+            builder.set_column_bounds(column, min..=max);
+        }
+
+        builder.set_initial_columns(self.list()?);
+        builder.set_initial_rows(self.list()?);
+
+        Ok(builder)
+    }
+
+    fn position(&mut self, randomized: bool) -> Result<Position, SnapshotError> {
+        match self.byte()? {
+            0 => return Ok(Position::Completed),
+            1 => return Ok(Position::Selected),
+            2 => {}
+            _ => return Err(SnapshotError::InvalidValue),
+        }
+
+        let mut levels = vec![];
+
+        for _ in 0..self.number()? {
+            let column = self.number()?;
+            let rng = match randomized {
+                true => Some(Rng::new(self.u64()?)),
+                false => None,
+            };
+
+            let step = match self.byte()? {
+                0 => LevelStep::Pending(self.number()?.checked_sub(1)),
+                1 => LevelStep::Entered(self.number()?.checked_sub(1)),
+                2 => LevelStep::Exhausted,
+                _ => return Err(SnapshotError::InvalidValue),
+            };
+
+            levels.push(Level { column, rng, step });
+        }
+
+        Ok(Position::Branched(levels))
+    }
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod tests {
+    use super::{Level, LevelStep, Position, Writer, MAGIC, VERSION};
+    use crate::rng::Rng;
+    use crate::tests::{n_queens, random_colored_row, sorted};
+    use crate::{ColumnChooser, MrvRandom, SnapshotError, Solver, SolverBuilder, SolverError};
+
+    /// Checks that snapshots taken after every step of the search continue it
+    /// with the same solutions in the same order
+    fn check_every_step<C: ColumnChooser + Clone>(solver: Solver<C>, resume: impl Fn(&[u8]) -> Solver<C>) {
+        let solutions = solver.clone().collect::<Vec<_>>();
+
+        let mut solver = solver;
+        let mut found = vec![];
+
+        loop {
+            let bytes = solver.snapshot();
+            let mut resumed = resume(&bytes);
+
+            let mut rest = found.clone();
+            rest.extend(resumed.by_ref());
+            assert_eq!(solutions, rest);
+            assert!(resumed.is_completed());
+
+            if solver.is_completed() {
+                break;
+            }
+            found.extend(solver.step());
+        }
+
+        assert_eq!(solutions, found);
+    }
+
+    #[test]
+    fn test_every_step() {
+        check_every_step(n_queens(6).build(), |bytes| Solver::resume(bytes).unwrap());
+
+        let mut builder = n_queens(6);
+        builder.set_seed(20);
+        check_every_step(builder.build(), |bytes| Solver::resume(bytes).unwrap());
+    }
+
+    #[test]
+    fn test_random_problems() {
+        let mut rng = Rng::new(20);
+
+        for _ in 0..100 {
+            let primary_count = 1 + rng.below(3);
+            let column_count = primary_count + rng.below(4);
+
+            let mut builder = SolverBuilder::new();
+            for _ in 0..1 + rng.below(12) {
+                builder.add_colored_row(random_colored_row(&mut rng, primary_count, column_count));
+            }
+            builder.set_secondary_columns((primary_count..column_count).collect());
+            builder.set_column_bounds(0, rng.below(2)..=2);
+            if rng.below(2) == 0 {
+                builder.set_seed(rng.next_u64());
+            }
+
+            let mut solver = builder.build();
+            if rng.below(3) == 0 {
+                solver.push_assumption(0);
+            }
+
+            check_every_step(solver, |bytes| Solver::resume(bytes).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_random_chooser() {
+        let mut solver = n_queens(7).build_randomized(20);
+        let solutions = sorted(solver.clone());
+
+        let mut found = solver.by_ref().take(10).collect::<Vec<_>>();
+        let bytes = solver.snapshot();
+        found.extend(Solver::resume_with_chooser(&bytes, MrvRandom::new(1)).unwrap());
+
+        assert_eq!(solutions, sorted(found));
+    }
+
+    #[test]
+    fn test_assumptions_and_edits() {
+        let mut solver = n_queens(6).build();
+        solver.remove_row(3);
+        solver.add_row(vec![2, 6 + 3]).unwrap();
+        solver.push_assumption(1);
+        solver.next();
+
+        let mut resumed = Solver::resume(&solver.snapshot()).unwrap();
+        assert_eq!(&[1], resumed.assumptions());
+        assert_eq!(solver.clone().collect::<Vec<_>>(), resumed.clone().collect::<Vec<_>>());
+
+        solver.pop_assumption();
+        resumed.pop_assumption();
+        assert_eq!(solver.collect::<Vec<_>>(), resumed.collect::<Vec<_>>());
+
+        // A primary column that no row contains leaves the problem without solutions
+        let mut solver = n_queens(6).build();
+        solver.add_column(100);
+        assert_eq!(Ok(0), Solver::resume(&solver.snapshot()).unwrap().count_solutions());
+    }
+
+    #[test]
+    fn test_sparse_columns() {
+        let mut builder = SolverBuilder::new();
+        builder.set_rows(vec![vec![0, 1 << 40], vec![0], vec![1 << 40], vec![5, 1 << 50], vec![5]]);
+        builder.set_secondary_columns(vec![1 << 50]);
+        let mut solver = builder.build();
+        solver.next();
+
+        let bytes = solver.snapshot();
+        assert_eq!(solver.collect::<Vec<_>>(), Solver::resume(&bytes).unwrap().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut solver = n_queens(4).build();
+        solver.next();
+        let bytes = solver.snapshot();
+
+        assert_eq!(Some(SnapshotError::UnknownFormat), Solver::resume(b"dlx\0\x01").err());
+        assert_eq!(Some(SnapshotError::UnexpectedEnd), Solver::resume(&bytes[..bytes.len() - 1]).err());
+        assert_eq!(Some(SnapshotError::InvalidValue), Solver::resume(&[&bytes[..], &[0]].concat()).err());
+
+        // The search branches on a column that does not exist
+        let mut writer = Writer(MAGIC.to_vec());
+        writer.byte(VERSION);
+        writer.builder(&n_queens(4));
+        writer.list(&[]);
+        writer.rng(None);
+        writer.position(&Position::Branched(vec![Level { column: 100, rng: None, step: LevelStep::Pending(Some(0)) }]));
+        assert_eq!(Some(SnapshotError::InvalidPosition), Solver::resume(&writer.0).err());
+
+        let resume_problem = |builder: &SolverBuilder| {
+            let mut writer = Writer(MAGIC.to_vec());
+            writer.byte(VERSION);
+            writer.builder(builder);
+            writer.list(&[]);
+            writer.rng(None);
+            writer.position(&Position::Completed);
+            Solver::resume(&writer.0).err()
+        };
+
+        let mut builder = SolverBuilder::new();
+        builder.add_row(vec![0]);
+        builder.add_row(vec![]);
+        assert_eq!(None, resume_problem(&builder));
+
+        builder.add_colored_row(vec![(0, None), (1, Some(1 << 40))]);
+        builder.set_secondary_columns(vec![1]);
+        assert_eq!(Some(SnapshotError::InvalidValue), resume_problem(&builder));
+
+        builder.rows[2] = vec![(0, None), (1, Some(0))];
+        builder.set_initial_columns(vec![2]);
+        assert_eq!(Some(SnapshotError::InvalidProblem(SolverError::UncoverableColumn { column: 2 })), resume_problem(&builder));
+    }
+}
@@ -0,0 +1,42 @@
+/// Counts of the work done by the search
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SearchStats {
+    /// Number of times a node has been removed from its column
+    pub updates: u64,
+    /// Number of rows tried at each depth of the search tree
+    pub nodes_per_depth: Vec<u64>,
+    /// Number of solutions found at each depth of the search tree
+    pub solutions_per_depth: Vec<u64>,
+    /// Number of rows removed from the solution to try another one
+    pub backtracks: u64,
+    /// Deepest level of the search tree reached
+    pub max_depth: usize,
+}
+
+impl SearchStats {
+    /// Total number of rows tried
+    pub fn nodes(&self) -> u64 {
+        self.nodes_per_depth.iter().sum()
+    }
+
+    /// Total number of solutions found
+    pub fn solutions(&self) -> u64 {
+        self.solutions_per_depth.iter().sum()
+    }
+
+    pub(crate) fn add_node(&mut self, depth: usize) {
+        increment_at(&mut self.nodes_per_depth, depth);
+        self.max_depth = self.max_depth.max(depth + 1);
+    }
+
+    pub(crate) fn add_solution(&mut self, depth: usize) {
+        increment_at(&mut self.solutions_per_depth, depth);
+    }
+}
+
+fn increment_at(counts: &mut Vec<u64>, index: usize) {
+    if counts.len() <= index {
+        counts.resize(index + 1, 0);
+    }
+    counts[index] += 1;
+}
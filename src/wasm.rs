@@ -3,14 +3,13 @@ use std::collections::{BTreeSet, VecDeque};
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
-use js_sys::Array;
+use js_sys::{Array, Function};
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
 #[derive(Default)]
 pub struct SolverBuilder {
-    rows: Vec<Vec<usize>>,
-    initial_columns: Vec<usize>,
+    builder: crate::SolverBuilder,
 }
 
 #[wasm_bindgen]
@@ -21,16 +20,28 @@ impl SolverBuilder {
     }
 
     pub fn add_row(&mut self, row: Vec<usize>) {
-        self.rows.push(row);
+        self.builder.add_row(row);
+    }
+
+    pub fn set_secondary_columns(&mut self, secondary_columns: Vec<usize>) {
+        self.builder.set_secondary_columns(secondary_columns);
     }
 
     pub fn set_initial_columns(&mut self, initial_columns: Vec<usize>) {
-        self.initial_columns = initial_columns;
+        self.builder.set_initial_columns(initial_columns);
+    }
+
+    pub fn set_initial_rows(&mut self, initial_rows: Vec<usize>) {
+        self.builder.set_initial_rows(initial_rows);
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        self.builder.set_seed(seed);
     }
 
     pub fn build(self) -> Solver {
         Solver {
-            solver: crate::Solver::new(self.rows, self.initial_columns),
+            solver: self.builder.build(),
         }
     }
 }
@@ -50,6 +61,19 @@ impl Solver {
     pub async fn all_solutions(self) -> Array {
         self.solver.map(into_js_array).collect()
     }
+
+    /// Finds all solutions like [`all_solutions`](Self::all_solutions), calling `on_progress`
+    /// every `interval` steps with the estimated fraction of the search that is done
+    pub async fn all_solutions_with_progress(self, interval: u64, on_progress: Function) -> Array {
+        self.solver
+            .with_progress(interval, 3, |progress| {
+                on_progress
+                    .call1(&JsValue::NULL, &JsValue::from(progress.fraction))
+                    .ok();
+            })
+            .map(into_js_array)
+            .collect()
+    }
 }
 
 fn into_js_array<T>(vec: Vec<T>) -> Array